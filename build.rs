@@ -0,0 +1,27 @@
+use std::{env, fs, path::Path};
+
+/// Bundles every `fingerprints/*.kfp` file, so that a release is shipped by adding its file
+fn main() {
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("fingerprints");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "kfp"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+
+    let includes: Vec<String> = files
+        .iter()
+        .map(|path| {
+            println!("cargo:rerun-if-changed={}", path.display());
+            format!("include_bytes!({:?})", path.display().to_string())
+        })
+        .collect();
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("fingerprints.rs");
+    fs::write(out, format!("&[{}]", includes.join(", "))).unwrap();
+}
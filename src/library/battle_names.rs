@@ -164,6 +164,18 @@ pub const STAGE_NAMES: [&str; 163] = [
     "Generic Landscape?",
 ];
 
+/// Battle flag names ordered from LSB to MSB
+pub const BATTLE_FLAG_NAMES: [&str; 8] = [
+    "Cannot escape",
+    "Disable victory fanfare",
+    "Show timer",
+    "No exp gained",
+    "Do not show exp. screen",
+    "Force surprise attack",
+    "Force back attack",
+    "Scripted battle",
+];
+
 pub const ENEMY_NAMES: [&str; 144] = [
     "Dummy",
     "GIM52A",
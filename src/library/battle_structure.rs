@@ -2,6 +2,15 @@ use std::io::Cursor;
 
 use binrw::{BinRead, BinWrite};
 
+/// Number of battle structures contained in scene.out
+pub const BATTLE_STRUCTURE_NUMBER: usize = 1024;
+
+/// Number of enemy slots of a battle structure
+pub const ENEMY_SLOT_NUMBER: usize = 8;
+
+/// Offset of the enemy ids stored in a packed battle structure
+const ENEMY_ID_OFFSET: u8 = 0x10;
+
 #[derive(BinRead, BinWrite, Debug)]
 #[brw(little)]
 pub struct PackedBattleStructure {
@@ -22,7 +31,7 @@ pub struct PackedBattleStructure {
     enemy_level: [u8; 8],
 }
 
-#[derive(BinRead, BinWrite, Debug, Clone)]
+#[derive(BinRead, BinWrite, Debug, Clone, PartialEq)]
 #[brw(little)]
 pub struct Coordinate {
     pub x: i16,
@@ -30,7 +39,7 @@ pub struct Coordinate {
     pub z: i16,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BattleStructure {
     pub stage_id: u8,
     pub flags: BattleFlags,
@@ -40,7 +49,7 @@ pub struct BattleStructure {
 }
 
 /// Flags ordered from LSB to MSB
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BattleFlags {
     pub cannot_escape: bool,
     pub disable_win_fanfare: bool,
@@ -52,7 +61,7 @@ pub struct BattleFlags {
     pub scripted_battle: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CameraAttributes {
     /// camera number of size u4
     pub number: u8,
@@ -61,7 +70,7 @@ pub struct CameraAttributes {
 }
 
 /// Enemy information where id is equal to PackedBattleStructure.id_enemies[idx] - 0x10
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Enemy {
     pub id: u8,
     pub level: u8,
@@ -76,6 +85,14 @@ pub struct Enemy {
     pub unknown_4: u8,
 }
 
+/// Change of a single field between two battle structures
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
 impl PackedBattleStructure {
 
     pub fn try_from_bytes(bytes: &[u8]) -> anyhow::Result<PackedBattleStructure> {
@@ -84,6 +101,22 @@ impl PackedBattleStructure {
         Ok(battle_structure_packed)
     }
 
+    /// Converts bytes from an untrusted source, like a shared code or file, rejecting the
+    /// enemy ids stored without their offset
+    pub fn try_into_battle_structure(self) -> anyhow::Result<BattleStructure> {
+        if let Some((slot, id)) = self
+            .id_enemies
+            .iter()
+            .enumerate()
+            .find(|(_, id)| **id < ENEMY_ID_OFFSET)
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid enemy id in slot {slot}: {id:#04x} is below {ENEMY_ID_OFFSET:#04x}"
+            ));
+        }
+        Ok(self.into_battle_structure())
+    }
+
     pub fn into_battle_structure(self) -> BattleStructure {
         BattleStructure {
             stage_id: self.stage_id,
@@ -134,7 +167,7 @@ impl PackedBattleStructure {
         let mask = 0x80 >> index;
 
         Enemy {
-            id: self.id_enemies[index] - 0x10,
+            id: self.id_enemies[index] - ENEMY_ID_OFFSET,
             level: self.enemy_level[index],
             enabled: (self.enabled_enemies & mask) > 0,
             not_loaded: (self.not_loaded_enemies & mask) > 0,
@@ -152,6 +185,14 @@ impl PackedBattleStructure {
 impl BattleStructure {
 
     pub fn as_packed_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut id_enemies = [0; ENEMY_SLOT_NUMBER];
+        for (slot, enemy) in self.enemies.iter().enumerate() {
+            id_enemies[slot] = enemy.id.checked_add(ENEMY_ID_OFFSET).ok_or(anyhow::anyhow!(
+                "Invalid enemy id in slot {slot}: {} (expected 0-{})",
+                enemy.id,
+                u8::MAX - ENEMY_ID_OFFSET
+            ))?;
+        }
         let packed_battle_structure = PackedBattleStructure {
             stage_id: self.stage_id,
             flags: self.packed_battle_flags(),
@@ -162,7 +203,7 @@ impl BattleStructure {
             not_targetable_enemies: self.packed_untargetable_enemies(),
             enabled_enemies: self.packed_enabled_enemies(),
             enemies_coords: self.enemies.each_ref().map(|enemy| enemy.coordinate.clone()),
-            id_enemies: self.enemies.each_ref().map(|enemy| enemy.id + 0x10),
+            id_enemies,
             unknown_1: self.enemies.each_ref().map(|enemy| enemy.unknown_1),
             unknown_2: self.enemies.each_ref().map(|enemy| enemy.unknown_2),
             unknown_3: self.enemies.each_ref().map(|enemy| enemy.unknown_3),
@@ -175,7 +216,55 @@ impl BattleStructure {
         Ok(writer.into_inner())
     }
 
-    fn packed_battle_flags(&self) -> u8 {
+    /// Name and displayed value of every editable field, in declaration order
+    pub fn field_values(&self) -> Vec<(String, String)> {
+        let mut fields = vec![
+            ("stage".to_string(), self.stage_id.to_string()),
+            ("cannot escape".to_string(), self.flags.cannot_escape.to_string()),
+            ("disable win fanfare".to_string(), self.flags.disable_win_fanfare.to_string()),
+            ("show timer".to_string(), self.flags.show_timer.to_string()),
+            ("no exp".to_string(), self.flags.no_exp.to_string()),
+            ("disable exp screen".to_string(), self.flags.disable_exp_screen.to_string()),
+            ("force surprise attack".to_string(), self.flags.force_surprise_attack.to_string()),
+            ("force back attack".to_string(), self.flags.force_back_attack.to_string()),
+            ("scripted battle".to_string(), self.flags.scripted_battle.to_string()),
+            ("main camera number".to_string(), self.main_camera.number.to_string()),
+            ("main camera animation".to_string(), self.main_camera.animation.to_string()),
+            ("secondary camera number".to_string(), self.secondary_camera.number.to_string()),
+            ("secondary camera animation".to_string(), self.secondary_camera.animation.to_string()),
+        ];
+
+        for (i, enemy) in self.enemies.iter().enumerate() {
+            fields.extend([
+                (format!("enemy {i} id"), enemy.id.to_string()),
+                (format!("enemy {i} level"), enemy.level.to_string()),
+                (format!("enemy {i} enabled"), enemy.enabled.to_string()),
+                (format!("enemy {i} not loaded"), enemy.not_loaded.to_string()),
+                (format!("enemy {i} invisible"), enemy.invisible.to_string()),
+                (format!("enemy {i} untargetable"), enemy.untargetable.to_string()),
+                (format!("enemy {i} x"), enemy.coordinate.x.to_string()),
+                (format!("enemy {i} y"), enemy.coordinate.y.to_string()),
+                (format!("enemy {i} z"), enemy.coordinate.z.to_string()),
+                (format!("enemy {i} unknown 1"), format!("{:#x}", enemy.unknown_1)),
+                (format!("enemy {i} unknown 2"), format!("{:#x}", enemy.unknown_2)),
+                (format!("enemy {i} unknown 3"), format!("{:#x}", enemy.unknown_3)),
+                (format!("enemy {i} unknown 4"), format!("{:#x}", enemy.unknown_4)),
+            ]);
+        }
+        fields
+    }
+
+    /// Fields whose value differs from `other`
+    pub fn changed_fields(&self, other: &BattleStructure) -> Vec<FieldChange> {
+        self.field_values()
+            .into_iter()
+            .zip(other.field_values())
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((name, old), (_, new))| FieldChange { name, old, new })
+            .collect()
+    }
+
+    pub fn packed_battle_flags(&self) -> u8 {
         let mut flags = 0u8;
         flags |= self.flags.cannot_escape as u8;
         flags |= (self.flags.disable_win_fanfare as u8) << 1;
@@ -222,23 +311,31 @@ impl BattleStructure {
 
 }
 
+/// Packed bytes of the encounter used by the tests
 #[cfg(test)]
-mod test {
-    use hex_literal::hex;
+pub(crate) const SAMPLE_BYTES: &[u8] = &hex_literal::hex!(
+    "0681 0013 0000 0080 4c04 0000 1cf3 9cff"
+    "0000 bce9 9001 0000 bce9 74f5 0000 bce9"
+    "5cf9 0000 bce9 a8fd 0000 bce9 68f7 0000"
+    "bce9 50fb 0000 bce9 5710 1010 1010 1010"
+    "707f c800 c800 c800 c800 c800 c800 c800"
+    "1701 c800 c800 c800 c800 c800 c800 c800"
+    "9004 60ea 60ea 60ea 60ea 60ea 60ea 60ea"
+    "0102 0202 0202 0202 ffff ffff ffff ffff"
+);
+
+/// Battle structure of `SAMPLE_BYTES`
+#[cfg(test)]
+pub(crate) fn sample_battle_structure() -> BattleStructure {
+    PackedBattleStructure::try_from_bytes(SAMPLE_BYTES)
+        .unwrap()
+        .into_battle_structure()
+}
 
+#[cfg(test)]
+mod test {
     use super::*;
 
-    const BYTES: &[u8] = &hex!(
-        "0681 0013 0000 0080 4c04 0000 1cf3 9cff"
-        "0000 bce9 9001 0000 bce9 74f5 0000 bce9"
-        "5cf9 0000 bce9 a8fd 0000 bce9 68f7 0000"
-        "bce9 50fb 0000 bce9 5710 1010 1010 1010"
-        "707f c800 c800 c800 c800 c800 c800 c800"
-        "1701 c800 c800 c800 c800 c800 c800 c800"
-        "9004 60ea 60ea 60ea 60ea 60ea 60ea 60ea"
-        "0102 0202 0202 0202 ffff ffff ffff ffff"
-    );
-
     #[test]
     fn verify_pattle_battle_structure_layout() {
         assert_eq!(size_of::<PackedBattleStructure>(), 128);
@@ -251,14 +348,14 @@ mod test {
 
     #[test]
     fn test_parser() {
-        let packed_battle_structure = PackedBattleStructure::try_from_bytes(BYTES).unwrap();
+        let packed_battle_structure = PackedBattleStructure::try_from_bytes(SAMPLE_BYTES).unwrap();
         println!("{:?}", packed_battle_structure);
         assert_eq!(packed_battle_structure.stage_id, 6);
     }
 
     #[test]
     fn test_parse_battle_structure() {
-        let packed_battle_structure = PackedBattleStructure::try_from_bytes(BYTES).unwrap();
+        let packed_battle_structure = PackedBattleStructure::try_from_bytes(SAMPLE_BYTES).unwrap();
         let battle_structure = packed_battle_structure.into_battle_structure();
         println!("{:?}", battle_structure);
         assert_eq!(battle_structure.stage_id, 6);
@@ -309,9 +406,48 @@ mod test {
 
     #[test]
     fn test_parser_and_writer() {
-        let packed_battle_structure = PackedBattleStructure::try_from_bytes(BYTES).unwrap();
+        let packed_battle_structure = PackedBattleStructure::try_from_bytes(SAMPLE_BYTES).unwrap();
         let battle_structure = packed_battle_structure.into_battle_structure();
-        assert_eq!(battle_structure.as_packed_bytes().unwrap(), BYTES);
+        assert_eq!(battle_structure.as_packed_bytes().unwrap(), SAMPLE_BYTES);
+    }
+
+    #[test]
+    fn test_invalid_enemy_ids() {
+        let mut bytes = SAMPLE_BYTES.to_vec();
+        bytes[57] = 0x0F;
+        let packed_battle_structure = PackedBattleStructure::try_from_bytes(&bytes).unwrap();
+        assert!(packed_battle_structure.try_into_battle_structure().is_err());
+
+        let mut battle_structure = sample_battle_structure();
+        battle_structure.enemies[1].id = 0xEF;
+        assert_eq!(battle_structure.as_packed_bytes().unwrap()[57], 0xFF);
+        battle_structure.enemies[1].id = 0xF0;
+        assert!(battle_structure.as_packed_bytes().is_err());
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let battle_structure = sample_battle_structure();
+        let mut edited = battle_structure.clone();
+        assert!(battle_structure.changed_fields(&edited).is_empty());
+
+        edited.flags.no_exp = true;
+        edited.enemies[2].coordinate.x = 10;
+        assert_eq!(
+            battle_structure.changed_fields(&edited),
+            vec![
+                FieldChange {
+                    name: "no exp".to_string(),
+                    old: "false".to_string(),
+                    new: "true".to_string(),
+                },
+                FieldChange {
+                    name: "enemy 2 x".to_string(),
+                    old: "400".to_string(),
+                    new: "10".to_string(),
+                },
+            ]
+        );
     }
 }
 
@@ -0,0 +1,238 @@
+use super::battle_structure::Enemy;
+
+/// Approximate position (x, y, z) where the party stands, enemies usually being placed at
+/// negative z
+pub const PARTY_POSITION: [f32; 3] = [0.0, 0.0, 3000.0];
+
+/// Default spacing of the battlefield grid, in game units
+pub const DEFAULT_GRID_SIZE: i32 = 100;
+
+const MIN_ZOOM: f32 = 0.005;
+const MAX_ZOOM: f32 = 2.0;
+
+/// How an enemy slot is drawn on the battlefield
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotState {
+    Enabled,
+    Disabled,
+    Hidden,
+    NotLoaded,
+}
+
+impl SlotState {
+    pub fn of(enemy: &Enemy) -> SlotState {
+        if !enemy.enabled {
+            SlotState::Disabled
+        } else if enemy.not_loaded {
+            SlotState::NotLoaded
+        } else if enemy.invisible {
+            SlotState::Hidden
+        } else {
+            SlotState::Enabled
+        }
+    }
+}
+
+/// Plane on which the battlefield is drawn. The Y axis points down as on the PlayStation,
+/// so that flying enemies, with a negative Y, are drawn above the ground in the side views.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// X/Z plane seen from above
+    TopDown,
+    /// X/Y plane seen from behind the party
+    Front,
+    /// Z/Y plane seen from the side
+    Side,
+}
+
+impl Projection {
+    pub const ALL: [Projection; 3] = [Projection::TopDown, Projection::Front, Projection::Side];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Projection::TopDown => "Top-down (X/Z)",
+            Projection::Front => "Front (X/Y)",
+            Projection::Side => "Side (Z/Y)",
+        }
+    }
+
+    /// Projects a battlefield point (x, y, z) on the plane
+    pub fn project(&self, [x, y, z]: [f32; 3]) -> [f32; 2] {
+        match self {
+            Projection::TopDown => [x, z],
+            Projection::Front => [x, y],
+            Projection::Side => [z, y],
+        }
+    }
+
+    /// Position of the enemy on the plane
+    pub fn position(&self, enemy: &Enemy) -> [f32; 2] {
+        let coordinate = &enemy.coordinate;
+        self.project([
+            coordinate.x as f32,
+            coordinate.y as f32,
+            coordinate.z as f32,
+        ])
+    }
+
+    /// Moves the enemy to the plane position. The side views only change its height.
+    pub fn move_enemy(&self, enemy: &mut Enemy, position: [f32; 2], grid_size: Option<i32>) {
+        match self {
+            Projection::TopDown => {
+                enemy.coordinate.x = snap(position[0], grid_size);
+                enemy.coordinate.z = snap(position[1], grid_size);
+            }
+            Projection::Front | Projection::Side => {
+                enemy.coordinate.y = snap(position[1], grid_size);
+            }
+        }
+    }
+
+    /// Index of the enemy closest to the plane point within `radius`, if any
+    pub fn enemy_at(&self, enemies: &[Enemy], point: [f32; 2], radius: f32) -> Option<usize> {
+        enemies
+            .iter()
+            .enumerate()
+            .map(|(i, enemy)| {
+                let position = self.position(enemy);
+                let distance = (position[0] - point[0]).hypot(position[1] - point[1]);
+                (i, distance)
+            })
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+    }
+}
+
+/// Pan and zoom of a view, mapping a projected battlefield point to screen points
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapView {
+    /// Battlefield point shown at the center of the canvas
+    pub center: [f32; 2],
+    /// Screen points per game unit
+    pub zoom: f32,
+}
+
+impl Default for MapView {
+    fn default() -> Self {
+        MapView {
+            center: [0.0, 0.0],
+            zoom: 0.04,
+        }
+    }
+}
+
+impl MapView {
+    /// Screen offset of the battlefield point from the canvas center
+    pub fn to_screen(&self, point: [f32; 2]) -> [f32; 2] {
+        [
+            (point[0] - self.center[0]) * self.zoom,
+            (point[1] - self.center[1]) * self.zoom,
+        ]
+    }
+
+    /// Battlefield point at the screen offset from the canvas center
+    pub fn to_battlefield(&self, offset: [f32; 2]) -> [f32; 2] {
+        [
+            self.center[0] + offset[0] / self.zoom,
+            self.center[1] + offset[1] / self.zoom,
+        ]
+    }
+
+    /// Moves the view by a screen delta
+    pub fn pan(&mut self, delta: [f32; 2]) {
+        self.center[0] -= delta[0] / self.zoom;
+        self.center[1] -= delta[1] / self.zoom;
+    }
+
+    /// Zooms by `factor`, keeping the battlefield point under the screen offset in place
+    pub fn zoom_at(&mut self, offset: [f32; 2], factor: f32) {
+        let anchor = self.to_battlefield(offset);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center = [
+            anchor[0] - offset[0] / self.zoom,
+            anchor[1] - offset[1] / self.zoom,
+        ];
+    }
+
+    /// Centers and zooms the view so that every point fits in a canvas of the given size
+    pub fn fit(&mut self, points: &[[f32; 2]], size: [f32; 2], margin: f32) {
+        let Some(first) = points.first() else {
+            return;
+        };
+        let (min, max) = points.iter().fold((*first, *first), |(min, max), point| {
+            (
+                [min[0].min(point[0]), min[1].min(point[1])],
+                [max[0].max(point[0]), max[1].max(point[1])],
+            )
+        });
+        self.center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        let width = (max[0] - min[0]).max(1.0);
+        let height = (max[1] - min[1]).max(1.0);
+        self.zoom = ((size[0] - 2.0 * margin) / width)
+            .min((size[1] - 2.0 * margin) / height)
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+/// Rounds the value to the nearest grid line, or to the nearest unit without a grid
+pub fn snap(value: f32, grid_size: Option<i32>) -> i16 {
+    let value = match grid_size.filter(|size| *size > 1) {
+        Some(size) => (value / size as f32).round() * size as f32,
+        None => value.round(),
+    };
+    value.clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    #[test]
+    fn test_map_view() {
+        let mut view = MapView {
+            center: [100.0, -200.0],
+            zoom: 0.5,
+        };
+        assert_eq!(view.to_screen([100.0, -200.0]), [0.0, 0.0]);
+        assert_eq!(view.to_screen([120.0, -100.0]), [10.0, 50.0]);
+        assert_eq!(view.to_battlefield([10.0, 50.0]), [120.0, -100.0]);
+
+        view.zoom_at([10.0, 50.0], 2.0);
+        assert_eq!(view.zoom, 1.0);
+        assert_eq!(view.to_screen([120.0, -100.0]), [10.0, 50.0]);
+
+        view.pan([10.0, 50.0]);
+        assert_eq!(view.to_screen([120.0, -100.0]), [20.0, 100.0]);
+
+        view.fit(&[[-1000.0, 0.0], [1000.0, -500.0]], [220.0, 220.0], 10.0);
+        assert_eq!(view.center, [0.0, -250.0]);
+        assert_eq!(view.zoom, 0.1);
+    }
+
+    #[test]
+    fn test_move_enemy() {
+        let battle_structure = sample_battle_structure();
+        let mut enemies = battle_structure.enemies.clone();
+        assert_eq!(SlotState::of(&enemies[0]), SlotState::Enabled);
+        assert_eq!(SlotState::of(&enemies[1]), SlotState::Disabled);
+        let top_down = Projection::TopDown;
+        assert_eq!(top_down.enemy_at(&enemies, [1090.0, -3310.0], 50.0), Some(0));
+        assert_eq!(top_down.enemy_at(&enemies, [0.0, 0.0], 50.0), None);
+
+        top_down.move_enemy(&mut enemies[0], [1234.0, -40000.0], Some(100));
+        assert_eq!(enemies[0].coordinate.x, 1200);
+        assert_eq!(enemies[0].coordinate.z, i16::MIN);
+        assert_eq!(snap(1234.4, None), 1234);
+
+        let side = Projection::Side;
+        assert_eq!(side.position(&enemies[0]), [i16::MIN as f32, 0.0]);
+        side.move_enemy(&mut enemies[0], [0.0, -420.0], None);
+        assert_eq!(enemies[0].coordinate.y, -420);
+        assert_eq!(enemies[0].coordinate.z, i16::MIN);
+        assert_eq!(Projection::Front.position(&enemies[0]), [1200.0, -420.0]);
+        assert_eq!(Projection::Front.project(PARTY_POSITION), [0.0, 0.0]);
+    }
+}
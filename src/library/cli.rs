@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+
+use super::battle_structure::{BATTLE_STRUCTURE_NUMBER, ENEMY_SLOT_NUMBER};
+
+pub const USAGE: &str = "Usage: kyactus-ff8 [FILE] [--encounter ID] [--slot SLOT] [--readonly]
+
+Arguments:
+  [FILE]              scene.out file to open
+
+Options:
+  -e, --encounter ID  Encounter to select (0-1023)
+  -s, --slot SLOT     Enemy slot to select (0-7)
+  -r, --readonly      Prevent any saving
+  -h, --help          Print this help";
+
+/// Command line arguments of the GUI
+#[derive(Debug, Default, PartialEq)]
+pub struct Arguments {
+    pub path: Option<PathBuf>,
+    pub encounter: Option<usize>,
+    pub slot: Option<usize>,
+    pub readonly: bool,
+    pub help: bool,
+}
+
+impl Arguments {
+    /// Parses the arguments, excluding the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Arguments> {
+        let mut arguments = Arguments::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| {
+                inline_value
+                    .map(|value| value.to_string())
+                    .or_else(|| args.next())
+                    .ok_or(anyhow::anyhow!("Missing value for {name}"))
+            };
+
+            match name.as_str() {
+                "-e" | "--encounter" => {
+                    arguments.encounter = Some(parse_index(
+                        &name,
+                        &value(&name)?,
+                        BATTLE_STRUCTURE_NUMBER,
+                    )?);
+                }
+                "-s" | "--slot" => {
+                    arguments.slot = Some(parse_index(&name, &value(&name)?, ENEMY_SLOT_NUMBER)?);
+                }
+                "-r" | "--readonly" => arguments.readonly = true,
+                "-h" | "--help" => arguments.help = true,
+                _ if name.starts_with('-') => {
+                    return Err(anyhow::anyhow!("Unknown option: {name}"));
+                }
+                _ if arguments.path.is_none() => arguments.path = Some(PathBuf::from(arg)),
+                _ => return Err(anyhow::anyhow!("Unexpected argument: {arg}")),
+            }
+        }
+
+        let selection = match (arguments.encounter, arguments.slot) {
+            (Some(_), _) => Some("--encounter"),
+            (_, Some(_)) => Some("--slot"),
+            _ => None,
+        };
+        if let (Some(name), None, false) = (selection, &arguments.path, arguments.help) {
+            return Err(anyhow::anyhow!("{name} requires a FILE to open"));
+        }
+        Ok(arguments)
+    }
+}
+
+fn parse_index(name: &str, value: &str, count: usize) -> anyhow::Result<usize> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|index| *index < count)
+        .ok_or(anyhow::anyhow!(
+            "Invalid value for {name}: {value} (expected 0-{})",
+            count - 1
+        ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Arguments> {
+        Arguments::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        assert_eq!(parse(&[]).unwrap(), Arguments::default());
+        assert_eq!(
+            parse(&["path/to/scene.out", "--encounter", "512", "--slot=3", "--readonly"]).unwrap(),
+            Arguments {
+                path: Some(PathBuf::from("path/to/scene.out")),
+                encounter: Some(512),
+                slot: Some(3),
+                readonly: true,
+                help: false,
+            }
+        );
+        assert_eq!(parse(&["-e", "1", "-h"]).unwrap().encounter, Some(1));
+    }
+
+    #[test]
+    fn test_parse_invalid_arguments() {
+        assert!(parse(&["--encounter", "1024"]).is_err());
+        assert!(parse(&["--slot", "8"]).is_err());
+        assert!(parse(&["--slot"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["a.out", "b.out"]).is_err());
+        assert!(parse(&["--encounter", "1"]).is_err());
+        assert!(parse(&["--readonly", "-s", "2"]).is_err());
+    }
+}
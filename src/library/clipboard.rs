@@ -0,0 +1,231 @@
+use super::{
+    battle_names::ENEMY_NAMES,
+    battle_structure::{BattleStructure, Enemy},
+    encounter_table::TableColumn,
+    enemy_slots::EnemyField,
+};
+
+/// First line of a copied encounter
+pub const ENCOUNTER_HEADER: &str = "[ff8 encounter]";
+const ENEMY_SLOT_HEADER: &str = "[ff8 enemy slot]";
+
+/// What the pasted text contained
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteKind {
+    Encounter,
+    EnemySlot(usize),
+}
+
+/// Battle structure resulting from a paste, to be previewed before replacing the original
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paste {
+    pub kind: PasteKind,
+    pub battle_structure: BattleStructure,
+}
+
+/// Part of the pasted text being read
+enum Section {
+    Encounter,
+    Slot(usize),
+}
+
+/// Encounter as `field = value` lines, with one section per enemy slot
+pub fn copy_encounter(battle_structure: &BattleStructure) -> String {
+    let mut lines = vec![ENCOUNTER_HEADER.to_string()];
+    for column in encounter_columns() {
+        let value = if column.is_bool() {
+            (column.value(battle_structure) > 0).to_string()
+        } else {
+            column.text(battle_structure)
+        };
+        let line = format!("{} = {value}", column.name().to_lowercase());
+        lines.push(match column.description(battle_structure) {
+            Some(description) => format!("{line}  # {description}"),
+            None => line,
+        });
+    }
+    for (slot, enemy) in battle_structure.enemies.iter().enumerate() {
+        lines.push(String::new());
+        lines.push(format!("[slot {slot}]"));
+        lines.extend(enemy_lines(enemy));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Single enemy slot as `field = value` lines
+pub fn copy_enemy(enemy: &Enemy) -> String {
+    let mut lines = vec![ENEMY_SLOT_HEADER.to_string()];
+    lines.extend(enemy_lines(enemy));
+    lines.join("\n") + "\n"
+}
+
+/// Applies the copied encounter, or the copied enemy slot to `slot`, on the battle structure.
+/// Fields missing from the text are kept, text after `#` is ignored.
+pub fn paste(text: &str, battle_structure: &BattleStructure, slot: usize) -> anyhow::Result<Paste> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let (kind, mut section) = match lines.next() {
+        Some((_, ENCOUNTER_HEADER)) => (PasteKind::Encounter, Section::Encounter),
+        Some((_, ENEMY_SLOT_HEADER)) => (PasteKind::EnemySlot(slot), Section::Slot(slot)),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "The clipboard does not contain an encounter or an enemy slot"
+            ))
+        }
+    };
+    if slot >= battle_structure.enemies.len() {
+        return Err(anyhow::anyhow!("Invalid enemy slot: {slot}"));
+    }
+
+    let mut battle_structure = battle_structure.clone();
+    for (number, line) in lines {
+        let on_line = |err: anyhow::Error| anyhow::anyhow!("Line {number}: {err}");
+        if kind == PasteKind::Encounter {
+            if let Some(slot) = line
+                .strip_prefix("[slot ")
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = match slot.trim().parse::<usize>() {
+                    Ok(slot) if slot < battle_structure.enemies.len() => Section::Slot(slot),
+                    _ => return Err(on_line(anyhow::anyhow!("Invalid enemy slot: {slot}"))),
+                };
+                continue;
+            }
+        }
+
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(on_line(anyhow::anyhow!("Expected `field = value`: {line}")));
+        };
+        let (name, value) = (name.trim().to_lowercase(), value.trim());
+        match section {
+            Section::Encounter => {
+                let column = encounter_columns()
+                    .find(|column| column.name().to_lowercase() == name)
+                    .ok_or_else(|| on_line(anyhow::anyhow!("Unknown encounter field: {name}")))?;
+                column.set(&mut battle_structure, value).map_err(on_line)?;
+            }
+            Section::Slot(slot) => {
+                let field = EnemyField::ALL
+                    .into_iter()
+                    .find(|field| field.name().to_lowercase() == name)
+                    .ok_or_else(|| on_line(anyhow::anyhow!("Unknown enemy field: {name}")))?;
+                let value = parse_enemy_value(field, value).map_err(on_line)?;
+                field
+                    .set(&mut battle_structure.enemies[slot], value)
+                    .map_err(on_line)?;
+            }
+        }
+    }
+    Ok(Paste {
+        kind,
+        battle_structure,
+    })
+}
+
+/// Columns of the fields of the encounter that are not part of an enemy slot
+fn encounter_columns() -> impl Iterator<Item = TableColumn> {
+    TableColumn::all().into_iter().filter(|column| {
+        !matches!(
+            column,
+            TableColumn::EnemyId(_)
+                | TableColumn::EnemyLevel(_)
+                | TableColumn::EnemyEnabled(_)
+                | TableColumn::EnemyNotLoaded(_)
+                | TableColumn::EnemyInvisible(_)
+                | TableColumn::EnemyUntargetable(_)
+        )
+    })
+}
+
+fn enemy_lines(enemy: &Enemy) -> Vec<String> {
+    EnemyField::ALL
+        .into_iter()
+        .map(|field| {
+            let name = field.name().to_lowercase();
+            let value = field.value(enemy);
+            match field {
+                EnemyField::Id => format!(
+                    "{name} = {value}  # {}",
+                    ENEMY_NAMES
+                        .get(value as usize)
+                        .unwrap_or(&"Invalid enemy id!")
+                ),
+                field if field.is_bool() => format!("{name} = {}", value > 0),
+                _ => format!("{name} = {value}"),
+            }
+        })
+        .collect()
+}
+
+/// Value of an enemy field: a number, `0x` prefixed hexadecimal, `true` or `false`
+fn parse_enemy_value(field: EnemyField, text: &str) -> anyhow::Result<i32> {
+    let value = match text.to_lowercase().as_str() {
+        "true" if field.is_bool() => Some(1),
+        "false" if field.is_bool() => Some(0),
+        text => match text.strip_prefix("0x") {
+            Some(hex) => i32::from_str_radix(hex, 16).ok(),
+            None => text.parse().ok(),
+        },
+    };
+    value.ok_or_else(|| anyhow::anyhow!("Invalid {} value: {text}", field.name()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    #[test]
+    fn test_copy_paste_encounter() {
+        let battle_structure = sample_battle_structure();
+        let mut target = battle_structure.clone();
+        target.stage_id = 20;
+        target.flags.cannot_escape = false;
+        target.enemies[5].coordinate.z = 0;
+        target.enemies[0].unknown_1 = 0;
+
+        let text = copy_encounter(&battle_structure);
+        assert!(text.starts_with("[ff8 encounter]\nstage = 6  # "));
+        assert!(text.contains("\n[slot 7]\nenemy = 0  # "));
+        let paste = paste(&text, &target, 3).unwrap();
+        assert_eq!(paste.kind, PasteKind::Encounter);
+        assert_eq!(paste.battle_structure, battle_structure);
+    }
+
+    #[test]
+    fn test_copy_paste_enemy() {
+        let battle_structure = sample_battle_structure();
+        let text = copy_enemy(&battle_structure.enemies[0]);
+        assert!(text.contains("\nenabled = true\n"));
+        assert!(text.contains("\nunknown 1 = 32624\n"));
+
+        let paste = paste(&text, &battle_structure, 4).unwrap();
+        assert_eq!(paste.kind, PasteKind::EnemySlot(4));
+        assert_eq!(
+            paste.battle_structure.enemies[4],
+            battle_structure.enemies[0]
+        );
+        assert_eq!(
+            paste.battle_structure.enemies[0],
+            battle_structure.enemies[0]
+        );
+
+        let partial = "[ff8 enemy slot]\nlevel = 12 # from chat\nunknown 4 = 0x10\n";
+        let paste = super::paste(partial, &battle_structure, 1).unwrap();
+        assert_eq!(paste.battle_structure.enemies[1].level, 12);
+        assert_eq!(paste.battle_structure.enemies[1].unknown_4, 0x10);
+        assert_eq!(
+            paste.battle_structure.enemies[1].id,
+            battle_structure.enemies[1].id
+        );
+
+        assert!(super::paste("level = 12", &battle_structure, 1).is_err());
+        assert!(super::paste("[ff8 enemy slot]\nlevel = 256", &battle_structure, 1).is_err());
+        assert!(super::paste("[ff8 enemy slot]\nspeed = 1", &battle_structure, 1).is_err());
+        assert!(super::paste("[ff8 encounter]\n[slot 8]", &battle_structure, 1).is_err());
+    }
+}
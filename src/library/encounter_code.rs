@@ -0,0 +1,101 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use super::battle_structure::{BattleStructure, PackedBattleStructure};
+
+/// Prefix of the codes, followed by the format version
+const CODE_PREFIX: &str = "FF8E";
+const CODE_VERSION: u8 = 1;
+const PACKED_SIZE: usize = 128;
+const CHECKSUM_SIZE: usize = 4;
+
+/// Whether the text looks like an encounter code, whatever its version
+pub fn is_encounter_code(text: &str) -> bool {
+    text.trim().starts_with(CODE_PREFIX)
+}
+
+/// Encodes the packed bytes of the encounter followed by their CRC-32 in URL-safe base64,
+/// e.g. `FF8E1-BoEAEwAAAIBM...`
+pub fn encode_encounter(battle_structure: &BattleStructure) -> anyhow::Result<String> {
+    let mut bytes = battle_structure.as_packed_bytes()?;
+    let checksum = crc32fast::hash(&bytes);
+    bytes.extend(checksum.to_le_bytes());
+    Ok(format!(
+        "{CODE_PREFIX}{CODE_VERSION}-{}",
+        URL_SAFE_NO_PAD.encode(bytes)
+    ))
+}
+
+/// Decodes an encounter code, ignoring the whitespace added when it is wrapped in a message
+pub fn decode_encounter(code: &str) -> anyhow::Result<BattleStructure> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let (version, data) = code
+        .strip_prefix(CODE_PREFIX)
+        .and_then(|code| code.split_once('-'))
+        .ok_or_else(|| anyhow::anyhow!("Not an encounter code"))?;
+    if version != CODE_VERSION.to_string() {
+        return Err(anyhow::anyhow!(
+            "Unsupported encounter code version: {version} (expected {CODE_VERSION})"
+        ));
+    }
+
+    let bytes = URL_SAFE_NO_PAD
+        .decode(data)
+        .map_err(|err| anyhow::anyhow!("Invalid encounter code: {err}"))?;
+    if bytes.len() != PACKED_SIZE + CHECKSUM_SIZE {
+        return Err(anyhow::anyhow!(
+            "Invalid encounter code: {} bytes instead of {}",
+            bytes.len(),
+            PACKED_SIZE + CHECKSUM_SIZE
+        ));
+    }
+    let (packed, checksum) = bytes.split_at(PACKED_SIZE);
+    if crc32fast::hash(packed).to_le_bytes() != checksum {
+        return Err(anyhow::anyhow!(
+            "Invalid encounter code: checksum mismatch, the code may be incomplete"
+        ));
+    }
+    PackedBattleStructure::try_from_bytes(packed)?
+        .try_into_battle_structure()
+        .map_err(|err| anyhow::anyhow!("Invalid encounter code: {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::{sample_battle_structure, SAMPLE_BYTES};
+
+    use super::*;
+
+    #[test]
+    fn test_encounter_code() {
+        let battle_structure = sample_battle_structure();
+        let code = encode_encounter(&battle_structure).unwrap();
+        assert!(code.starts_with("FF8E1-BoEAEwAAAIBM"));
+        assert_eq!(code.len(), "FF8E1-".len() + 176);
+        assert!(is_encounter_code(&code));
+        assert_eq!(decode_encounter(&code).unwrap(), battle_structure);
+
+        let (start, end) = code.split_at(40);
+        let wrapped = format!("  {start}\n{end} ");
+        assert_eq!(decode_encounter(&wrapped).unwrap(), battle_structure);
+    }
+
+    #[test]
+    fn test_invalid_encounter_code() {
+        let battle_structure = sample_battle_structure();
+        let code = encode_encounter(&battle_structure).unwrap();
+
+        assert!(decode_encounter("hello").is_err());
+        assert!(decode_encounter(&code.replacen("FF8E1", "FF8E2", 1)).is_err());
+        assert!(decode_encounter(&code[..code.len() - 4]).is_err());
+        let mut corrupted = code.into_bytes();
+        corrupted[20] = if corrupted[20] == b'A' { b'B' } else { b'A' };
+        assert!(decode_encounter(&String::from_utf8(corrupted).unwrap()).is_err());
+
+        // crafted code with a valid checksum but an enemy id below its offset
+        let mut bytes = SAMPLE_BYTES.to_vec();
+        bytes[57] = 0x00;
+        bytes.extend(crc32fast::hash(&bytes).to_le_bytes());
+        let crafted = format!("FF8E1-{}", URL_SAFE_NO_PAD.encode(bytes));
+        assert!(decode_encounter(&crafted).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::Path;
+
+use super::{
+    battle_names::STAGE_NAMES,
+    battle_structure::{BattleStructure, PackedBattleStructure},
+    clipboard::{copy_encounter, paste, PasteKind, ENCOUNTER_HEADER},
+};
+
+const PACKED_SIZE: usize = 128;
+/// Metadata line of the text files giving the encounter the file was exported from
+const SOURCE_PREFIX: &str = "# source encounter:";
+const STAGE_PREFIX: &str = "# stage:";
+
+/// Format of a file holding a single encounter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncounterFileFormat {
+    /// The 128 packed bytes, as stored in scene.out
+    Binary,
+    /// `field = value` lines with the stage and enemy names, as copied to the clipboard
+    Text,
+}
+
+impl EncounterFileFormat {
+    pub fn from_path(path: &Path) -> Option<EncounterFileFormat> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "bin" => Some(EncounterFileFormat::Binary),
+            "txt" => Some(EncounterFileFormat::Text),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            EncounterFileFormat::Binary => "bin",
+            EncounterFileFormat::Text => "txt",
+        }
+    }
+}
+
+/// Encounter read from a standalone file
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncounterFile {
+    /// Encounter the file was exported from, if known
+    pub source_index: Option<usize>,
+    pub battle_structure: BattleStructure,
+}
+
+impl EncounterFile {
+    pub fn stage_name(&self) -> &'static str {
+        STAGE_NAMES
+            .get(self.battle_structure.stage_id as usize)
+            .unwrap_or(&"Unknown stage")
+    }
+}
+
+/// Default file name of an exported encounter, e.g. `encounter_0120.bin`. The binary format
+/// has no room for metadata, so the source encounter is only kept in its file name.
+pub fn encounter_file_name(index: usize, format: EncounterFileFormat) -> String {
+    format!("encounter_{index:04}.{}", format.extension())
+}
+
+/// Contents of the file of the encounter
+pub fn write_encounter_file(
+    index: usize,
+    battle_structure: &BattleStructure,
+    format: EncounterFileFormat,
+) -> anyhow::Result<Vec<u8>> {
+    match format {
+        EncounterFileFormat::Binary => battle_structure.as_packed_bytes(),
+        EncounterFileFormat::Text => {
+            let stage_name = STAGE_NAMES
+                .get(battle_structure.stage_id as usize)
+                .unwrap_or(&"Unknown stage");
+            let text = format!(
+                "{SOURCE_PREFIX} {index}\n{STAGE_PREFIX} {stage_name}\n{}",
+                copy_encounter(battle_structure)
+            );
+            Ok(text.into_bytes())
+        }
+    }
+}
+
+/// Whether the file holds a single encounter: 128 bytes for a binary file, or text starting
+/// with the metadata or the header of an encounter. Other files, e.g. a whole scene.out named
+/// `.bin`, are not encounter files whatever their extension.
+pub fn is_encounter_file(file_name: &str, bytes: &[u8]) -> bool {
+    match EncounterFileFormat::from_path(Path::new(file_name)) {
+        Some(EncounterFileFormat::Binary) => bytes.len() == PACKED_SIZE,
+        Some(EncounterFileFormat::Text) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|text| text.lines().map(str::trim).find(|line| !line.is_empty()))
+            .is_some_and(|line| {
+                line.starts_with(ENCOUNTER_HEADER) || line.starts_with(SOURCE_PREFIX)
+            }),
+        None => false,
+    }
+}
+
+/// Reads the encounter file named `file_name`. Fields missing from a text file are taken from
+/// `target`, the encounter it is imported into.
+pub fn read_encounter_file(
+    file_name: &str,
+    bytes: &[u8],
+    target: &BattleStructure,
+) -> anyhow::Result<EncounterFile> {
+    let format = EncounterFileFormat::from_path(Path::new(file_name))
+        .ok_or_else(|| anyhow::anyhow!("Not an encounter file: {file_name}"))?;
+    match format {
+        EncounterFileFormat::Binary => {
+            if bytes.len() != PACKED_SIZE {
+                return Err(anyhow::anyhow!(
+                    "Invalid encounter file {file_name}: {} bytes instead of {PACKED_SIZE}",
+                    bytes.len()
+                ));
+            }
+            let source_index = file_name
+                .strip_prefix("encounter_")
+                .and_then(|name| name.split('.').next())
+                .and_then(|index| index.parse().ok());
+            let battle_structure = PackedBattleStructure::try_from_bytes(bytes)?
+                .try_into_battle_structure()
+                .map_err(|err| anyhow::anyhow!("Invalid encounter file {file_name}: {err}"))?;
+            Ok(EncounterFile {
+                source_index,
+                battle_structure,
+            })
+        }
+        EncounterFileFormat::Text => {
+            let text = std::str::from_utf8(bytes)
+                .map_err(|_| anyhow::anyhow!("Invalid encounter file {file_name}: not text"))?;
+            let source_index = text
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(SOURCE_PREFIX))
+                .and_then(|index| index.trim().parse().ok());
+            let pasted = paste(text, target, 0)
+                .map_err(|err| anyhow::anyhow!("Invalid encounter file {file_name}: {err}"))?;
+            if pasted.kind != PasteKind::Encounter {
+                return Err(anyhow::anyhow!(
+                    "Invalid encounter file {file_name}: it holds an enemy slot"
+                ));
+            }
+            Ok(EncounterFile {
+                source_index,
+                battle_structure: pasted.battle_structure,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::{sample_battle_structure, SAMPLE_BYTES};
+
+    use super::*;
+
+    #[test]
+    fn test_encounter_files() {
+        let battle_structure = sample_battle_structure();
+        let mut target = battle_structure.clone();
+        target.stage_id = 1;
+        target.enemies[0].enabled = false;
+
+        for format in [EncounterFileFormat::Binary, EncounterFileFormat::Text] {
+            let file_name = encounter_file_name(120, format);
+            let bytes = write_encounter_file(120, &battle_structure, format).unwrap();
+            let file = read_encounter_file(&file_name, &bytes, &target).unwrap();
+            assert_eq!(file.source_index, Some(120));
+            assert_eq!(file.battle_structure, battle_structure);
+            assert_eq!(file.stage_name(), STAGE_NAMES[6]);
+        }
+
+        let bytes = write_encounter_file(120, &battle_structure, EncounterFileFormat::Binary);
+        assert_eq!(bytes.unwrap(), SAMPLE_BYTES);
+        let file = read_encounter_file("renamed.bin", SAMPLE_BYTES, &target).unwrap();
+        assert_eq!(file.source_index, None);
+
+        let text = write_encounter_file(120, &battle_structure, EncounterFileFormat::Text);
+        let text = String::from_utf8(text.unwrap()).unwrap();
+        assert!(text.starts_with(&format!(
+            "# source encounter: 120\n# stage: {}\n[ff8 encounter]\n",
+            STAGE_NAMES[6]
+        )));
+    }
+
+    #[test]
+    fn test_is_encounter_file() {
+        let text = write_encounter_file(120, &sample_battle_structure(), EncounterFileFormat::Text);
+        let text = text.unwrap();
+        assert!(is_encounter_file("encounter_0120.txt", &text));
+        assert!(is_encounter_file(
+            "a.txt",
+            copy_encounter(&sample_battle_structure()).as_bytes()
+        ));
+        assert!(is_encounter_file("encounter_0120.bin", SAMPLE_BYTES));
+
+        assert!(!is_encounter_file("scene.bin", &[0; 128 * 1024]));
+        assert!(!is_encounter_file(
+            "notes.txt",
+            b"some notes\n[ff8 encounter]"
+        ));
+        assert!(!is_encounter_file("a.out", SAMPLE_BYTES));
+    }
+
+    #[test]
+    fn test_invalid_encounter_files() {
+        let battle_structure = sample_battle_structure();
+        assert!(read_encounter_file("a.bin", &SAMPLE_BYTES[1..], &battle_structure).is_err());
+        assert!(read_encounter_file("a.out", SAMPLE_BYTES, &battle_structure).is_err());
+        let mut invalid_id = SAMPLE_BYTES.to_vec();
+        invalid_id[57] = 0x00;
+        assert!(read_encounter_file("a.bin", &invalid_id, &battle_structure).is_err());
+        assert!(read_encounter_file("a.txt", b"stage = 1", &battle_structure).is_err());
+        let slot = b"[ff8 enemy slot]\nlevel = 1";
+        assert!(read_encounter_file("a.txt", slot, &battle_structure).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use super::{
+    battle_names::{ENEMY_NAMES, STAGE_NAMES},
+    battle_structure::BattleStructure,
+};
+
+/// Criteria used to search the encounters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncounterFilter {
+    /// Text searched in the encounter id, stage name and enabled enemy names
+    pub text: String,
+    pub stage_id: Option<u8>,
+    /// Enemy id that must be present among the enabled enemies
+    pub enemy_id: Option<u8>,
+    /// Flags that must be set, packed as `BattleStructure::packed_battle_flags`
+    pub required_flags: u8,
+}
+
+impl EncounterFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == EncounterFilter::default()
+    }
+
+    pub fn matches(&self, index: usize, battle_structure: &BattleStructure) -> bool {
+        if self
+            .stage_id
+            .is_some_and(|stage_id| stage_id != battle_structure.stage_id)
+        {
+            return false;
+        }
+        if self.enemy_id.is_some_and(|enemy_id| {
+            !enabled_enemies(battle_structure).any(|enemy| enemy == enemy_id)
+        }) {
+            return false;
+        }
+        if battle_structure.packed_battle_flags() & self.required_flags != self.required_flags {
+            return false;
+        }
+
+        let text = self.text.trim().to_lowercase();
+        text.is_empty()
+            || encounter_summary(index, battle_structure)
+                .to_lowercase()
+                .contains(&text)
+    }
+
+    /// Indexes of the matching encounters
+    pub fn filter(&self, battle_structure_list: &[BattleStructure]) -> Vec<usize> {
+        battle_structure_list
+            .iter()
+            .enumerate()
+            .filter(|(i, battle_structure)| self.matches(*i, battle_structure))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// One-line description of the encounter, e.g. `12 - Dollet Bridge: Bite Bug, Bite Bug`
+pub fn encounter_summary(index: usize, battle_structure: &BattleStructure) -> String {
+    let enemy_names: Vec<&str> = enabled_enemies(battle_structure)
+        .map(|id| {
+            *ENEMY_NAMES
+                .get(id as usize)
+                .unwrap_or(&"Invalid enemy name!")
+        })
+        .collect();
+    format!(
+        "{index} - {}: {}",
+        STAGE_NAMES
+            .get(battle_structure.stage_id as usize)
+            .unwrap_or(&"Invalid Stage Id!"),
+        if enemy_names.is_empty() {
+            "no enemies".to_string()
+        } else {
+            enemy_names.join(", ")
+        }
+    )
+}
+
+fn enabled_enemies(battle_structure: &BattleStructure) -> impl Iterator<Item = u8> + '_ {
+    battle_structure
+        .enemies
+        .iter()
+        .filter(|enemy| enemy.enabled)
+        .map(|enemy| enemy.id)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    fn battle_structure_list() -> Vec<BattleStructure> {
+        let battle_structure = sample_battle_structure();
+        let mut other = battle_structure.clone();
+        other.stage_id = 1;
+        other.flags.cannot_escape = false;
+        other.enemies[1].enabled = true;
+        other.enemies[1].id = 2;
+        vec![battle_structure, other]
+    }
+
+    #[test]
+    fn test_encounter_summary() {
+        let list = battle_structure_list();
+        assert_eq!(
+            encounter_summary(0, &list[0]),
+            format!("0 - {}: {}", STAGE_NAMES[6], ENEMY_NAMES[71])
+        );
+        assert_eq!(
+            encounter_summary(1, &list[1]),
+            format!("1 - Dollet Bridge: {}, Blobra", ENEMY_NAMES[71])
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let list = battle_structure_list();
+        assert_eq!(EncounterFilter::default().filter(&list), vec![0, 1]);
+
+        let text_filter = EncounterFilter {
+            text: "  dollet BRIDGE ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(text_filter.filter(&list), vec![1]);
+
+        let stage_filter = EncounterFilter {
+            stage_id: Some(6),
+            ..Default::default()
+        };
+        assert_eq!(stage_filter.filter(&list), vec![0]);
+
+        let enemy_filter = EncounterFilter {
+            enemy_id: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(enemy_filter.filter(&list), vec![1]);
+
+        let flags_filter = EncounterFilter {
+            required_flags: 0b1000_0001,
+            ..Default::default()
+        };
+        assert_eq!(flags_filter.filter(&list), vec![0]);
+    }
+}
@@ -0,0 +1,371 @@
+use super::{
+    battle_names::{BATTLE_FLAG_NAMES, ENEMY_NAMES, STAGE_NAMES},
+    battle_structure::{BattleStructure, ENEMY_SLOT_NUMBER},
+};
+
+/// Highest value of the 4-bit camera attributes
+const MAX_CAMERA_VALUE: u8 = 0xF;
+
+/// Column of the encounter table, i.e. a single editable value of a battle structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableColumn {
+    Stage,
+    /// Battle flag, by bit ordered from LSB to MSB
+    Flag(u8),
+    MainCameraNumber,
+    MainCameraAnimation,
+    SecondaryCameraNumber,
+    SecondaryCameraAnimation,
+    EnemyId(usize),
+    EnemyLevel(usize),
+    EnemyEnabled(usize),
+    EnemyNotLoaded(usize),
+    EnemyInvisible(usize),
+    EnemyUntargetable(usize),
+}
+
+impl TableColumn {
+    /// Every column, in display order
+    pub fn all() -> Vec<TableColumn> {
+        let mut columns = vec![TableColumn::Stage];
+        columns.extend((0..BATTLE_FLAG_NAMES.len() as u8).map(TableColumn::Flag));
+        columns.extend([
+            TableColumn::MainCameraNumber,
+            TableColumn::MainCameraAnimation,
+            TableColumn::SecondaryCameraNumber,
+            TableColumn::SecondaryCameraAnimation,
+        ]);
+        for slot in 0..ENEMY_SLOT_NUMBER {
+            columns.extend([
+                TableColumn::EnemyId(slot),
+                TableColumn::EnemyLevel(slot),
+                TableColumn::EnemyEnabled(slot),
+                TableColumn::EnemyNotLoaded(slot),
+                TableColumn::EnemyInvisible(slot),
+                TableColumn::EnemyUntargetable(slot),
+            ]);
+        }
+        columns
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            TableColumn::Stage => "Stage".to_string(),
+            TableColumn::Flag(bit) => BATTLE_FLAG_NAMES[*bit as usize].to_string(),
+            TableColumn::MainCameraNumber => "Main camera".to_string(),
+            TableColumn::MainCameraAnimation => "Main camera anim.".to_string(),
+            TableColumn::SecondaryCameraNumber => "Secondary camera".to_string(),
+            TableColumn::SecondaryCameraAnimation => "Secondary camera anim.".to_string(),
+            TableColumn::EnemyId(slot) => format!("Enemy {slot}"),
+            TableColumn::EnemyLevel(slot) => format!("Enemy {slot} level"),
+            TableColumn::EnemyEnabled(slot) => format!("Enemy {slot} enabled"),
+            TableColumn::EnemyNotLoaded(slot) => format!("Enemy {slot} not loaded"),
+            TableColumn::EnemyInvisible(slot) => format!("Enemy {slot} invisible"),
+            TableColumn::EnemyUntargetable(slot) => format!("Enemy {slot} untargetable"),
+        }
+    }
+
+    /// Whether the column holds a flag, shown as a checkbox
+    pub fn is_bool(&self) -> bool {
+        matches!(
+            self,
+            TableColumn::Flag(_)
+                | TableColumn::EnemyEnabled(_)
+                | TableColumn::EnemyNotLoaded(_)
+                | TableColumn::EnemyInvisible(_)
+                | TableColumn::EnemyUntargetable(_)
+        )
+    }
+
+    /// Numeric value of the cell, flags being 0 or 1
+    pub fn value(&self, battle_structure: &BattleStructure) -> u8 {
+        let enemies = &battle_structure.enemies;
+        match self {
+            TableColumn::Stage => battle_structure.stage_id,
+            TableColumn::Flag(bit) => (battle_structure.packed_battle_flags() >> bit) & 1,
+            TableColumn::MainCameraNumber => battle_structure.main_camera.number,
+            TableColumn::MainCameraAnimation => battle_structure.main_camera.animation,
+            TableColumn::SecondaryCameraNumber => battle_structure.secondary_camera.number,
+            TableColumn::SecondaryCameraAnimation => battle_structure.secondary_camera.animation,
+            TableColumn::EnemyId(slot) => enemies[*slot].id,
+            TableColumn::EnemyLevel(slot) => enemies[*slot].level,
+            TableColumn::EnemyEnabled(slot) => enemies[*slot].enabled as u8,
+            TableColumn::EnemyNotLoaded(slot) => enemies[*slot].not_loaded as u8,
+            TableColumn::EnemyInvisible(slot) => enemies[*slot].invisible as u8,
+            TableColumn::EnemyUntargetable(slot) => enemies[*slot].untargetable as u8,
+        }
+    }
+
+    /// Stage or enemy name of the cell, if any
+    pub fn description(&self, battle_structure: &BattleStructure) -> Option<&'static str> {
+        match self {
+            TableColumn::Stage => STAGE_NAMES.get(battle_structure.stage_id as usize).copied(),
+            TableColumn::EnemyId(slot) => ENEMY_NAMES
+                .get(battle_structure.enemies[*slot].id as usize)
+                .copied(),
+            _ => None,
+        }
+    }
+
+    /// Value of the cell as copied to the clipboard
+    pub fn text(&self, battle_structure: &BattleStructure) -> String {
+        self.value(battle_structure).to_string()
+    }
+
+    /// Sets the cell from a number, a stage or enemy name, or a flag such as `true` or `x`
+    pub fn set(&self, battle_structure: &mut BattleStructure, text: &str) -> anyhow::Result<()> {
+        let text = text.trim();
+        let value = match self {
+            TableColumn::Stage => parse_id(text, &STAGE_NAMES)?,
+            TableColumn::EnemyId(_) => parse_id(text, &ENEMY_NAMES)?,
+            TableColumn::EnemyLevel(_) => parse_number(text, u8::MAX)?,
+            TableColumn::MainCameraNumber
+            | TableColumn::MainCameraAnimation
+            | TableColumn::SecondaryCameraNumber
+            | TableColumn::SecondaryCameraAnimation => parse_number(text, MAX_CAMERA_VALUE)?,
+            _ => parse_bool(text)? as u8,
+        };
+
+        let flags = &mut battle_structure.flags;
+        let enemies = &mut battle_structure.enemies;
+        match self {
+            TableColumn::Stage => battle_structure.stage_id = value,
+            TableColumn::Flag(bit) => {
+                let flag = match bit {
+                    0 => &mut flags.cannot_escape,
+                    1 => &mut flags.disable_win_fanfare,
+                    2 => &mut flags.show_timer,
+                    3 => &mut flags.no_exp,
+                    4 => &mut flags.disable_exp_screen,
+                    5 => &mut flags.force_surprise_attack,
+                    6 => &mut flags.force_back_attack,
+                    _ => &mut flags.scripted_battle,
+                };
+                *flag = value > 0;
+            }
+            TableColumn::MainCameraNumber => battle_structure.main_camera.number = value,
+            TableColumn::MainCameraAnimation => battle_structure.main_camera.animation = value,
+            TableColumn::SecondaryCameraNumber => battle_structure.secondary_camera.number = value,
+            TableColumn::SecondaryCameraAnimation => {
+                battle_structure.secondary_camera.animation = value
+            }
+            TableColumn::EnemyId(slot) => enemies[*slot].id = value,
+            TableColumn::EnemyLevel(slot) => enemies[*slot].level = value,
+            TableColumn::EnemyEnabled(slot) => enemies[*slot].enabled = value > 0,
+            TableColumn::EnemyNotLoaded(slot) => enemies[*slot].not_loaded = value > 0,
+            TableColumn::EnemyInvisible(slot) => enemies[*slot].invisible = value > 0,
+            TableColumn::EnemyUntargetable(slot) => enemies[*slot].untargetable = value > 0,
+        }
+        Ok(())
+    }
+
+    /// Whether the cell matches a column filter: a comparison such as `>= 50`,
+    /// or a case-insensitive text searched in the value and its name
+    pub fn matches(&self, battle_structure: &BattleStructure, filter: &str) -> bool {
+        let filter = filter.trim();
+        if filter.is_empty() {
+            return true;
+        }
+
+        let value = self.value(battle_structure);
+        for (operator, compare) in COMPARISONS {
+            if let Some(Ok(operand)) = filter
+                .strip_prefix(operator)
+                .map(|operand| operand.trim().parse::<u8>())
+            {
+                return compare(value, operand);
+            }
+        }
+
+        let filter = filter.to_lowercase();
+        value.to_string() == filter
+            || self
+                .description(battle_structure)
+                .is_some_and(|name| name.to_lowercase().contains(&filter))
+    }
+}
+
+type Comparison = fn(u8, u8) -> bool;
+
+/// Comparison operators of the column filters, longest first so that `>=` is not read as `>`
+const COMPARISONS: [(&str, Comparison); 5] = [
+    (">=", |value, operand| value >= operand),
+    ("<=", |value, operand| value <= operand),
+    (">", |value, operand| value > operand),
+    ("<", |value, operand| value < operand),
+    ("=", |value, operand| value == operand),
+];
+
+fn parse_number(text: &str, max: u8) -> anyhow::Result<u8> {
+    text.parse::<u8>()
+        .ok()
+        .filter(|value| *value <= max)
+        .ok_or(anyhow::anyhow!("Invalid value: {text} (expected 0-{max})"))
+}
+
+fn parse_id(text: &str, names: &[&str]) -> anyhow::Result<u8> {
+    if let Some(i) = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(text))
+    {
+        return Ok(i as u8);
+    }
+    parse_number(text, (names.len() - 1) as u8)
+}
+
+fn parse_bool(text: &str) -> anyhow::Result<bool> {
+    match text.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "x" => Ok(true),
+        "0" | "false" | "no" | "" => Ok(false),
+        _ => Err(anyhow::anyhow!("Invalid value: {text} (expected 0 or 1)")),
+    }
+}
+
+/// Indexes of the encounters matching every column filter, `filters` being aligned with `columns`
+pub fn filter_rows(
+    battle_structure_list: &[BattleStructure],
+    columns: &[TableColumn],
+    filters: &[String],
+) -> Vec<usize> {
+    (0..battle_structure_list.len())
+        .filter(|i| {
+            columns
+                .iter()
+                .zip(filters)
+                .all(|(column, filter)| column.matches(&battle_structure_list[*i], filter))
+        })
+        .collect()
+}
+
+/// Sorts the rows by the column value, keeping the encounter order between equal values
+pub fn sort_rows(
+    battle_structure_list: &[BattleStructure],
+    rows: &mut [usize],
+    column: TableColumn,
+    descending: bool,
+) {
+    rows.sort_by(|a, b| {
+        let ordering = column
+            .value(&battle_structure_list[*a])
+            .cmp(&column.value(&battle_structure_list[*b]));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Tab separated values of the cells, one line per row, as copied by spreadsheets
+pub fn copy_cells(
+    battle_structure_list: &[BattleStructure],
+    rows: &[usize],
+    columns: &[TableColumn],
+) -> String {
+    rows.iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| column.text(&battle_structure_list[*row]))
+                .collect::<Vec<String>>()
+                .join("\t")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Pastes tab separated values starting at the given cell, the cells past the last row or
+/// column being ignored. Returns the changed battle structures, or the first invalid cell.
+pub fn paste_cells(
+    battle_structure_list: &[BattleStructure],
+    rows: &[usize],
+    columns: &[TableColumn],
+    (first_row, first_column): (usize, usize),
+    text: &str,
+) -> anyhow::Result<Vec<(usize, BattleStructure)>> {
+    let mut changes: Vec<(usize, BattleStructure)> = Vec::new();
+    for (line, row) in text.lines().zip(rows.iter().skip(first_row)) {
+        let mut battle_structure = battle_structure_list[*row].clone();
+        for (cell, column) in line.split('\t').zip(columns.iter().skip(first_column)) {
+            column
+                .set(&mut battle_structure, cell)
+                .map_err(|err| anyhow::anyhow!("Encounter {row}, {}: {err}", column.name()))?;
+        }
+        changes.push((*row, battle_structure));
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    fn battle_structure_list() -> Vec<BattleStructure> {
+        let battle_structure = sample_battle_structure();
+        let mut other = battle_structure.clone();
+        other.stage_id = 1;
+        other.enemies[0].level = 10;
+        vec![battle_structure, other]
+    }
+
+    #[test]
+    fn test_set_cell() {
+        let mut battle_structure = battle_structure_list().remove(0);
+        TableColumn::Stage.set(&mut battle_structure, "dollet bridge").unwrap();
+        assert_eq!(battle_structure.stage_id, 1);
+        TableColumn::Flag(3).set(&mut battle_structure, "x").unwrap();
+        assert!(battle_structure.flags.no_exp);
+        TableColumn::EnemyLevel(2).set(&mut battle_structure, " 42 ").unwrap();
+        assert_eq!(TableColumn::EnemyLevel(2).value(&battle_structure), 42);
+
+        assert!(TableColumn::MainCameraNumber.set(&mut battle_structure, "16").is_err());
+        assert!(TableColumn::EnemyId(0)
+            .set(&mut battle_structure, &ENEMY_NAMES.len().to_string())
+            .is_err());
+        assert!(TableColumn::EnemyEnabled(0).set(&mut battle_structure, "maybe").is_err());
+    }
+
+    #[test]
+    fn test_filter_and_sort_rows() {
+        let list = battle_structure_list();
+        let columns = [TableColumn::Stage, TableColumn::EnemyLevel(0)];
+        let filters = |stage: &str, level: &str| [stage.to_string(), level.to_string()];
+
+        assert_eq!(filter_rows(&list, &columns, &filters("", "")), vec![0, 1]);
+        assert_eq!(filter_rows(&list, &columns, &filters("bridge", "")), vec![1]);
+        assert_eq!(filter_rows(&list, &columns, &filters("6", "")), vec![0]);
+        assert_eq!(filter_rows(&list, &columns, &filters("", ">= 255")), vec![0]);
+        assert_eq!(filter_rows(&list, &columns, &filters("", "<100")), vec![1]);
+
+        let mut rows = vec![0, 1];
+        sort_rows(&list, &mut rows, TableColumn::Stage, false);
+        assert_eq!(rows, vec![1, 0]);
+        sort_rows(&list, &mut rows, TableColumn::Stage, true);
+        assert_eq!(rows, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_copy_paste_cells() {
+        let list = battle_structure_list();
+        let columns = TableColumn::all();
+        let level = columns
+            .iter()
+            .position(|column| *column == TableColumn::EnemyLevel(0))
+            .unwrap();
+
+        let copied = copy_cells(&list, &[0, 1], &columns[level..level + 2]);
+        assert_eq!(copied, "255\t1\n10\t1");
+
+        let changes = paste_cells(&list, &[1, 0], &columns, (0, level), "20\t0\n30\n40").unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].0, 1);
+        assert_eq!(changes[0].1.enemies[0].level, 20);
+        assert!(!changes[0].1.enemies[0].enabled);
+        assert_eq!(changes[1].0, 0);
+        assert_eq!(changes[1].1.enemies[0].level, 30);
+
+        assert!(paste_cells(&list, &[0], &columns, (0, level), "300").is_err());
+    }
+}
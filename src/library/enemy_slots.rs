@@ -0,0 +1,258 @@
+use std::ops::RangeInclusive;
+
+use super::{
+    battle_names::ENEMY_NAMES,
+    battle_structure::{BattleStructure, Enemy},
+};
+
+/// Field of an enemy slot that can be set on several slots at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyField {
+    Id,
+    Level,
+    Enabled,
+    NotLoaded,
+    Invisible,
+    Untargetable,
+    X,
+    Y,
+    Z,
+    Unknown1,
+    Unknown2,
+    Unknown3,
+    Unknown4,
+}
+
+/// Enemy slots affected by a bulk set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotSelection {
+    All,
+    Enabled,
+    /// The slot chosen in the enemy list
+    Selected(usize),
+}
+
+impl SlotSelection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlotSelection::All => "all slots",
+            SlotSelection::Enabled => "enabled slots",
+            SlotSelection::Selected(_) => "selected slot",
+        }
+    }
+
+    /// Whether the enemy slot is part of the selection
+    pub fn contains(&self, slot: usize, enemy: &Enemy) -> bool {
+        match self {
+            SlotSelection::All => true,
+            SlotSelection::Enabled => enemy.enabled,
+            SlotSelection::Selected(selected) => *selected == slot,
+        }
+    }
+}
+
+impl EnemyField {
+    pub const ALL: [EnemyField; 13] = [
+        EnemyField::Id,
+        EnemyField::Level,
+        EnemyField::Enabled,
+        EnemyField::NotLoaded,
+        EnemyField::Invisible,
+        EnemyField::Untargetable,
+        EnemyField::X,
+        EnemyField::Y,
+        EnemyField::Z,
+        EnemyField::Unknown1,
+        EnemyField::Unknown2,
+        EnemyField::Unknown3,
+        EnemyField::Unknown4,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyField::Id => "Enemy",
+            EnemyField::Level => "Level",
+            EnemyField::Enabled => "Enabled",
+            EnemyField::NotLoaded => "NOT loaded",
+            EnemyField::Invisible => "NOT visible",
+            EnemyField::Untargetable => "NOT targetable",
+            EnemyField::X => "X",
+            EnemyField::Y => "Y",
+            EnemyField::Z => "Z",
+            EnemyField::Unknown1 => "Unknown 1",
+            EnemyField::Unknown2 => "Unknown 2",
+            EnemyField::Unknown3 => "Unknown 3",
+            EnemyField::Unknown4 => "Unknown 4",
+        }
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(
+            self,
+            EnemyField::Enabled
+                | EnemyField::NotLoaded
+                | EnemyField::Invisible
+                | EnemyField::Untargetable
+        )
+    }
+
+    /// Valid values of the field, flags being 0 or 1
+    pub fn range(&self) -> RangeInclusive<i32> {
+        match self {
+            EnemyField::Id => 0..=ENEMY_NAMES.len() as i32 - 1,
+            EnemyField::Level | EnemyField::Unknown4 => 0..=u8::MAX as i32,
+            EnemyField::X | EnemyField::Y | EnemyField::Z => i16::MIN as i32..=i16::MAX as i32,
+            EnemyField::Unknown1 | EnemyField::Unknown2 | EnemyField::Unknown3 => {
+                0..=u16::MAX as i32
+            }
+            _ => 0..=1,
+        }
+    }
+
+    pub fn value(&self, enemy: &Enemy) -> i32 {
+        match self {
+            EnemyField::Id => enemy.id as i32,
+            EnemyField::Level => enemy.level as i32,
+            EnemyField::Enabled => enemy.enabled as i32,
+            EnemyField::NotLoaded => enemy.not_loaded as i32,
+            EnemyField::Invisible => enemy.invisible as i32,
+            EnemyField::Untargetable => enemy.untargetable as i32,
+            EnemyField::X => enemy.coordinate.x as i32,
+            EnemyField::Y => enemy.coordinate.y as i32,
+            EnemyField::Z => enemy.coordinate.z as i32,
+            EnemyField::Unknown1 => enemy.unknown_1 as i32,
+            EnemyField::Unknown2 => enemy.unknown_2 as i32,
+            EnemyField::Unknown3 => enemy.unknown_3 as i32,
+            EnemyField::Unknown4 => enemy.unknown_4 as i32,
+        }
+    }
+
+    pub fn set(&self, enemy: &mut Enemy, value: i32) -> anyhow::Result<()> {
+        let range = self.range();
+        if !range.contains(&value) {
+            return Err(anyhow::anyhow!(
+                "Invalid {} value: {value} (expected {}-{})",
+                self.name(),
+                range.start(),
+                range.end()
+            ));
+        }
+
+        match self {
+            EnemyField::Id => enemy.id = value as u8,
+            EnemyField::Level => enemy.level = value as u8,
+            EnemyField::Enabled => enemy.enabled = value > 0,
+            EnemyField::NotLoaded => enemy.not_loaded = value > 0,
+            EnemyField::Invisible => enemy.invisible = value > 0,
+            EnemyField::Untargetable => enemy.untargetable = value > 0,
+            EnemyField::X => enemy.coordinate.x = value as i16,
+            EnemyField::Y => enemy.coordinate.y = value as i16,
+            EnemyField::Z => enemy.coordinate.z = value as i16,
+            EnemyField::Unknown1 => enemy.unknown_1 = value as u16,
+            EnemyField::Unknown2 => enemy.unknown_2 = value as u16,
+            EnemyField::Unknown3 => enemy.unknown_3 = value as u16,
+            EnemyField::Unknown4 => enemy.unknown_4 = value as u8,
+        }
+        Ok(())
+    }
+}
+
+/// Sets the field of every selected enemy slot, returning the number of slots set
+pub fn bulk_set(
+    battle_structure: &mut BattleStructure,
+    field: EnemyField,
+    value: i32,
+    selection: SlotSelection,
+) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for (_, enemy) in battle_structure
+        .enemies
+        .iter_mut()
+        .enumerate()
+        .filter(|(slot, enemy)| selection.contains(*slot, enemy))
+    {
+        field.set(enemy, value)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Swaps two enemy slots with all their fields, the packed bitmasks following the slots
+pub fn swap_slots(battle_structure: &mut BattleStructure, a: usize, b: usize) {
+    battle_structure.enemies.swap(a, b);
+}
+
+/// Moves an enemy slot to another position, shifting the slots in between
+pub fn move_slot(battle_structure: &mut BattleStructure, from: usize, to: usize) {
+    let enemies = &mut battle_structure.enemies;
+    if from < to {
+        enemies[from..=to].rotate_left(1);
+    } else if to < from {
+        enemies[to..=from].rotate_right(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::{sample_battle_structure, PackedBattleStructure};
+
+    use super::*;
+
+    #[test]
+    fn test_bulk_set() {
+        let mut battle_structure = sample_battle_structure();
+        battle_structure.enemies[3].enabled = true;
+
+        let count =
+            bulk_set(&mut battle_structure, EnemyField::Level, 42, SlotSelection::Enabled).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(battle_structure.enemies[0].level, 42);
+        assert_eq!(battle_structure.enemies[3].level, 42);
+        assert_eq!(battle_structure.enemies[1].level, 255);
+
+        let count = bulk_set(&mut battle_structure, EnemyField::X, -500, SlotSelection::All).unwrap();
+        assert_eq!(count, 8);
+        assert!(battle_structure
+            .enemies
+            .iter()
+            .all(|enemy| enemy.coordinate.x == -500));
+
+        let count =
+            bulk_set(&mut battle_structure, EnemyField::Y, 7, SlotSelection::Selected(5)).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(battle_structure.enemies[5].coordinate.y, 7);
+        assert_ne!(battle_structure.enemies[4].coordinate.y, 7);
+
+        assert!(bulk_set(&mut battle_structure, EnemyField::Level, 256, SlotSelection::All).is_err());
+        assert!(bulk_set(&mut battle_structure, EnemyField::Id, 200, SlotSelection::All).is_err());
+    }
+
+    #[test]
+    fn test_reorder_slots() {
+        let original = sample_battle_structure();
+        let mut battle_structure = original.clone();
+        battle_structure.enemies[0].invisible = true;
+        battle_structure.enemies[0].untargetable = true;
+        let first = battle_structure.enemies[0].clone();
+
+        swap_slots(&mut battle_structure, 0, 5);
+        assert_eq!(battle_structure.enemies[5], first);
+        assert_eq!(battle_structure.enemies[0], original.enemies[5]);
+        let bytes = battle_structure.as_packed_bytes().unwrap();
+        // invisible, not loaded, untargetable and enabled bitmasks, slot 0 being the MSB
+        assert_eq!(bytes[4..8], [0b0000_0100, 0, 0b0000_0100, 0b0000_0100]);
+        let repacked = PackedBattleStructure::try_from_bytes(&bytes)
+            .unwrap()
+            .into_battle_structure();
+        assert_eq!(repacked, battle_structure);
+
+        move_slot(&mut battle_structure, 5, 1);
+        assert_eq!(battle_structure.enemies[1], first);
+        assert_eq!(battle_structure.enemies[2], original.enemies[1]);
+        assert_eq!(battle_structure.enemies[5], original.enemies[4]);
+        move_slot(&mut battle_structure, 1, 5);
+        swap_slots(&mut battle_structure, 0, 5);
+        assert_eq!(battle_structure.enemies[0], first);
+        assert_eq!(battle_structure.enemies[1..], original.enemies[1..]);
+    }
+}
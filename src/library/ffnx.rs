@@ -0,0 +1,181 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    battle_names::STAGE_NAMES,
+    battle_structure::{BattleStructure, FieldChange},
+    scene_file::write_atomic,
+};
+
+/// Name of the manifest written next to the `direct` folder
+pub const MANIFEST_FILE_NAME: &str = "kyactus-manifest.txt";
+
+/// Path of scene.out relative to the game data overridden by mods.
+/// English files live in the unlocalized `battle` folder, the other languages
+/// in the `lang-xx` folder of the Steam release.
+pub fn data_scene_path(language: Option<&str>) -> PathBuf {
+    match language {
+        Some(code) if !is_default_language(code) => PathBuf::from(format!(
+            "lang-{}",
+            code.to_lowercase()
+        ))
+        .join("battle")
+        .join("scene.out"),
+        _ => PathBuf::from("battle").join("scene.out"),
+    }
+}
+
+/// Path of scene.out relative to the game folder when loaded by FFNx direct mode
+pub fn direct_scene_path(language: Option<&str>) -> PathBuf {
+    PathBuf::from("direct").join(data_scene_path(language))
+}
+
+/// Whether the code names a language folder of the game, two lowercase letters such as `fr`
+pub fn is_language_code(code: &str) -> bool {
+    code.len() == 2 && code.bytes().all(|c| c.is_ascii_lowercase())
+}
+
+/// Writes scene.out (and the manifest, if any) inside the output folder using the FFNx direct
+/// mode layout. Returns the path of the written scene.out.
+pub fn export_direct(
+    output_folder: &Path,
+    language: Option<&str>,
+    scene_bytes: &[u8],
+    manifest: Option<&str>,
+) -> anyhow::Result<PathBuf> {
+    if let Some(code) = language.filter(|code| !is_language_code(code)) {
+        return Err(anyhow::anyhow!(
+            "Invalid language code: {code:?}, expected two lowercase letters such as \"fr\""
+        ));
+    }
+    let scene_path = output_folder.join(direct_scene_path(language));
+    if let Some(parent) = scene_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_atomic(&scene_path, scene_bytes)?;
+
+    if let Some(manifest) = manifest {
+        write_atomic(&output_folder.join(MANIFEST_FILE_NAME), manifest.as_bytes())?;
+    }
+    Ok(scene_path)
+}
+
+/// Human readable description of the changes made to the battle structures
+pub fn manifest(
+    language: Option<&str>,
+    original: &[BattleStructure],
+    edited: &[BattleStructure],
+    modified_from_vanilla: Option<(&str, Vec<usize>)>,
+) -> String {
+    let mut manifest = String::new();
+    _ = writeln!(manifest, "Kyactus - FFVIII Battle Structure Editor");
+    _ = writeln!(
+        manifest,
+        "File: {}",
+        direct_scene_path(language).to_string_lossy().replace('\\', "/")
+    );
+    _ = writeln!(manifest, "Language: {}", language.unwrap_or("en"));
+
+    if let Some((baseline_name, indexes)) = modified_from_vanilla {
+        _ = writeln!(manifest);
+        _ = writeln!(
+            manifest,
+            "Encounters modified from vanilla ({baseline_name}): {}",
+            indexes.len()
+        );
+        for index in indexes {
+            _ = writeln!(manifest, "  {index} ({})", stage_name(edited.get(index)));
+        }
+    }
+
+    let changes: Vec<(usize, Vec<FieldChange>)> = original
+        .iter()
+        .zip(edited)
+        .map(|(original, edited)| original.changed_fields(edited))
+        .enumerate()
+        .filter(|(_, fields)| !fields.is_empty())
+        .collect();
+
+    _ = writeln!(manifest);
+    _ = writeln!(manifest, "Encounters changed in this session: {}", changes.len());
+    for (index, fields) in changes {
+        _ = writeln!(manifest, "  {index} ({})", stage_name(edited.get(index)));
+        for field in fields {
+            _ = writeln!(manifest, "    {}: {} -> {}", field.name, field.old, field.new);
+        }
+    }
+    manifest
+}
+
+fn is_default_language(code: &str) -> bool {
+    code.eq_ignore_ascii_case("en") || code.eq_ignore_ascii_case("eng")
+}
+
+fn stage_name(battle_structure: Option<&BattleStructure>) -> &'static str {
+    battle_structure
+        .and_then(|battle_structure| STAGE_NAMES.get(battle_structure.stage_id as usize))
+        .unwrap_or(&"Invalid Stage Id!")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    #[test]
+    fn test_direct_scene_path() {
+        assert_eq!(
+            direct_scene_path(None),
+            Path::new("direct").join("battle").join("scene.out")
+        );
+        assert_eq!(direct_scene_path(Some("en")), direct_scene_path(None));
+        assert_eq!(
+            direct_scene_path(Some("FR")),
+            Path::new("direct")
+                .join("lang-fr")
+                .join("battle")
+                .join("scene.out")
+        );
+    }
+
+    #[test]
+    fn test_export_direct() {
+        let output = tempfile::tempdir().unwrap();
+        let scene_path =
+            export_direct(output.path(), Some("it"), &[1, 2, 3], Some("manifest")).unwrap();
+
+        assert_eq!(scene_path, output.path().join(direct_scene_path(Some("it"))));
+        assert_eq!(std::fs::read(scene_path).unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            std::fs::read_to_string(output.path().join(MANIFEST_FILE_NAME)).unwrap(),
+            "manifest"
+        );
+    }
+
+    #[test]
+    fn test_invalid_language_code() {
+        assert!(is_language_code("de"));
+        assert!(!is_language_code("DE"));
+        assert!(!is_language_code("../x"));
+
+        let output = tempfile::tempdir().unwrap();
+        assert!(export_direct(output.path(), Some("../../x"), &[1], None).is_err());
+        assert_eq!(output.path().read_dir().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_manifest() {
+        let original = vec![sample_battle_structure(); 3];
+        let mut edited = original.clone();
+        edited[1].enemies[0].level = 30;
+
+        let manifest = manifest(None, &original, &edited, Some(("EN", vec![1])));
+        assert!(manifest.contains("Encounters modified from vanilla (EN): 1"));
+        assert!(manifest.contains("Encounters changed in this session: 1"));
+        assert!(manifest.contains("  1 (Dollet City ? (Spice Spice Shop))"));
+        assert!(manifest.contains("    enemy 0 level: 255 -> 30"));
+    }
+}
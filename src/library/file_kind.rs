@@ -0,0 +1,63 @@
+use std::path::Path;
+
+/// Kind of a file given to the editor, e.g. by drag and drop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileKind {
+    /// Whole scene.out
+    Scene,
+    /// Vanilla fingerprints exported by the editor
+    Fingerprints,
+    /// Game archives (e.g. battle.fs) or mod archives
+    Archive,
+    /// Patch to apply on top of an opened scene.out
+    Patch,
+    /// Single encounter exported by the editor, if its contents match (see `is_encounter_file`)
+    Encounter,
+    /// Anything else, opened as a scene.out
+    Unknown,
+}
+
+impl FileKind {
+    pub fn from_path(path: &Path) -> FileKind {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "out" => FileKind::Scene,
+            "kfp" => FileKind::Fingerprints,
+            "fs" | "fi" | "fl" | "zip" | "iro" => FileKind::Archive,
+            "ips" | "bps" | "xdelta" | "ppf" => FileKind::Patch,
+            "bin" | "txt" => FileKind::Encounter,
+            _ => FileKind::Unknown,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileKind::Scene => "scene.out",
+            FileKind::Fingerprints => "fingerprints",
+            FileKind::Archive => "archive",
+            FileKind::Patch => "patch",
+            FileKind::Encounter => "encounter",
+            FileKind::Unknown => "unknown",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file_kind_from_path() {
+        assert_eq!(FileKind::from_path(Path::new("a/scene.out")), FileKind::Scene);
+        assert_eq!(FileKind::from_path(Path::new("SCENE.OUT")), FileKind::Scene);
+        assert_eq!(FileKind::from_path(Path::new("en.kfp")), FileKind::Fingerprints);
+        assert_eq!(FileKind::from_path(Path::new("battle.fs")), FileKind::Archive);
+        assert_eq!(FileKind::from_path(Path::new("mod.ips")), FileKind::Patch);
+        assert_eq!(FileKind::from_path(Path::new("encounter_0120.bin")), FileKind::Encounter);
+        assert_eq!(FileKind::from_path(Path::new("scene")), FileKind::Unknown);
+    }
+}
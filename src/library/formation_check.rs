@@ -0,0 +1,189 @@
+use super::{battle_structure::BattleStructure, stage_geometry::StageGeometry};
+
+/// Default distance under which two enabled enemies are considered overlapping
+pub const DEFAULT_OVERLAP_RADIUS: f32 = 500.0;
+
+/// Playable area used when the stage geometry is not available, enemies being placed in front
+/// of the party
+pub const DEFAULT_BOUNDS: Bounds = Bounds {
+    min: [-5000, -9000],
+    max: [5000, 0],
+};
+
+/// Height difference from the ground under which a stage polygon is considered part of the floor
+const GROUND_TOLERANCE: i16 = 100;
+
+/// Rectangle of the battlefield plane, as (x, z)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: [i16; 2],
+    pub max: [i16; 2],
+}
+
+impl Bounds {
+    pub fn contains(&self, x: i16, z: i16) -> bool {
+        (self.min[0]..=self.max[0]).contains(&x) && (self.min[1]..=self.max[1]).contains(&z)
+    }
+
+    /// Extent of the floor of the stage, i.e. of the polygons lying at ground level
+    pub fn from_geometry(geometry: &StageGeometry) -> Option<Bounds> {
+        geometry
+            .polygons
+            .iter()
+            .filter(|polygon| {
+                polygon
+                    .iter()
+                    .all(|vertex| vertex[1].abs() <= GROUND_TOLERANCE)
+            })
+            .flatten()
+            .fold(None, |bounds: Option<Bounds>, vertex| {
+                let [x, _, z] = *vertex;
+                Some(match bounds {
+                    Some(bounds) => Bounds {
+                        min: [bounds.min[0].min(x), bounds.min[1].min(z)],
+                        max: [bounds.max[0].max(x), bounds.max[1].max(z)],
+                    },
+                    None => Bounds {
+                        min: [x, z],
+                        max: [x, z],
+                    },
+                })
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    /// Too close to another enabled slot
+    Overlap { other_slot: usize, distance: f32 },
+    /// Outside the playable area
+    OutOfBounds,
+}
+
+/// Problem found on an enabled enemy slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormationWarning {
+    pub slot: usize,
+    pub kind: WarningKind,
+}
+
+impl FormationWarning {
+    pub fn message(&self) -> String {
+        match &self.kind {
+            WarningKind::Overlap {
+                other_slot,
+                distance,
+            } => format!(
+                "Slot {} overlaps slot {other_slot} ({distance:.0} units apart)",
+                self.slot
+            ),
+            WarningKind::OutOfBounds => format!("Slot {} is outside the playable area", self.slot),
+        }
+    }
+}
+
+/// Warnings of the enabled enemy slots, overlapping ones being reported on both slots
+pub fn check_formation(
+    battle_structure: &BattleStructure,
+    overlap_radius: f32,
+    bounds: &Bounds,
+) -> Vec<FormationWarning> {
+    let enabled: Vec<usize> = (0..battle_structure.enemies.len())
+        .filter(|slot| battle_structure.enemies[*slot].enabled)
+        .collect();
+
+    let mut warnings = Vec::new();
+    for slot in enabled.iter().copied() {
+        let coordinate = &battle_structure.enemies[slot].coordinate;
+        if !bounds.contains(coordinate.x, coordinate.z) {
+            warnings.push(FormationWarning {
+                slot,
+                kind: WarningKind::OutOfBounds,
+            });
+        }
+        for other_slot in enabled.iter().copied().filter(|other| *other != slot) {
+            let other = &battle_structure.enemies[other_slot].coordinate;
+            let distance = ((coordinate.x as f32 - other.x as f32).powi(2)
+                + (coordinate.y as f32 - other.y as f32).powi(2)
+                + (coordinate.z as f32 - other.z as f32).powi(2))
+            .sqrt();
+            if distance < overlap_radius {
+                warnings.push(FormationWarning {
+                    slot,
+                    kind: WarningKind::Overlap {
+                        other_slot,
+                        distance,
+                    },
+                });
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    #[test]
+    fn test_check_formation() {
+        let mut battle_structure = sample_battle_structure();
+        assert!(check_formation(&battle_structure, 500.0, &DEFAULT_BOUNDS).is_empty());
+
+        battle_structure.enemies[1].enabled = true;
+        battle_structure.enemies[1].coordinate.x = 1100;
+        battle_structure.enemies[1].coordinate.y = 300;
+        battle_structure.enemies[1].coordinate.z = -3300;
+        battle_structure.enemies[2].coordinate.z = 1000;
+        let warnings = check_formation(&battle_structure, 500.0, &DEFAULT_BOUNDS);
+        assert_eq!(
+            warnings,
+            vec![
+                FormationWarning {
+                    slot: 0,
+                    kind: WarningKind::Overlap {
+                        other_slot: 1,
+                        distance: 300.0
+                    }
+                },
+                FormationWarning {
+                    slot: 1,
+                    kind: WarningKind::Overlap {
+                        other_slot: 0,
+                        distance: 300.0
+                    }
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[0].message(),
+            "Slot 0 overlaps slot 1 (300 units apart)"
+        );
+        assert!(check_formation(&battle_structure, 200.0, &DEFAULT_BOUNDS).is_empty());
+
+        battle_structure.enemies[0].coordinate.z = 1000;
+        let warnings = check_formation(&battle_structure, 200.0, &DEFAULT_BOUNDS);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::OutOfBounds);
+    }
+
+    #[test]
+    fn test_bounds_from_geometry() {
+        let geometry = StageGeometry {
+            polygons: vec![
+                vec![[-2000, 0, -6000], [2000, 50, -6000], [0, 0, 500]],
+                vec![[-9000, -3000, -9000], [9000, -3000, 9000], [0, -3000, 0]],
+            ],
+        };
+        assert_eq!(
+            Bounds::from_geometry(&geometry),
+            Some(Bounds {
+                min: [-2000, -6000],
+                max: [2000, 500],
+            })
+        );
+        assert_eq!(Bounds::from_geometry(&StageGeometry::default()), None);
+    }
+}
@@ -0,0 +1,596 @@
+use std::path::{Path, PathBuf};
+
+use ab_glyph::{Font, FontArc, PxScale, ScaleFont};
+
+use super::{
+    battle_names::{BATTLE_FLAG_NAMES, ENEMY_NAMES, STAGE_NAMES},
+    battle_structure::BattleStructure,
+    battlefield::{MapView, Projection, SlotState, PARTY_POSITION},
+    scene_file::write_atomic,
+    xml::escape_xml,
+};
+
+pub const DIAGRAM_WIDTH: u32 = 640;
+/// Height of the plot and of the legend below it, listing up to all the battle flags
+pub const DIAGRAM_HEIGHT: u32 = 632;
+const PLOT_HEIGHT: f32 = 480.0;
+const PLOT_MARGIN: f32 = 60.0;
+/// Spacing of the grid lines of the plot, in game units
+const GRID_SIZE: f32 = 1000.0;
+const MARKER_RADIUS: f32 = 11.0;
+const FONT_SIZE: f32 = 13.0;
+const TITLE_SIZE: f32 = 16.0;
+/// Columns of the battle flags in the legend, after the `Flags:` label
+const FLAG_COLUMNS: usize = 2;
+const FLAG_OFFSET: f32 = 70.0;
+const FLAG_COLUMN_WIDTH: f32 = 260.0;
+
+const BACKGROUND_COLOR: Rgb = [255, 255, 255];
+const TEXT_COLOR: Rgb = [30, 30, 30];
+const GRID_COLOR: Rgb = [225, 225, 225];
+const AXIS_COLOR: Rgb = [170, 170, 170];
+const ENABLED_COLOR: Rgb = [80, 180, 80];
+const NOT_LOADED_COLOR: Rgb = [200, 120, 60];
+const PARTY_COLOR: Rgb = [80, 140, 220];
+
+pub type Rgb = [u8; 3];
+
+/// Image format of an exported diagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Png,
+    Svg,
+}
+
+impl DiagramFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DiagramFormat::Png => "png",
+            DiagramFormat::Svg => "svg",
+        }
+    }
+}
+
+/// Element of a diagram, in image pixels
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Line {
+        from: [f32; 2],
+        to: [f32; 2],
+        width: f32,
+        color: Rgb,
+    },
+    Circle {
+        center: [f32; 2],
+        radius: f32,
+        fill: Option<Rgb>,
+        stroke: Option<(f32, Rgb)>,
+    },
+    /// Text on the baseline at `position`, horizontally centered on it when `centered`
+    Text {
+        position: [f32; 2],
+        size: f32,
+        color: Rgb,
+        text: String,
+        centered: bool,
+    },
+}
+
+/// Top-down plot of the enabled enemy slots of the encounter, with a legend of its stage and
+/// flags below it
+pub fn diagram_shapes(index: usize, battle_structure: &BattleStructure) -> Vec<Shape> {
+    let projection = Projection::TopDown;
+    let enemies: Vec<(usize, _)> = battle_structure
+        .enemies
+        .iter()
+        .enumerate()
+        .filter(|(_, enemy)| enemy.enabled)
+        .collect();
+
+    let party = projection.project(PARTY_POSITION);
+    let mut points: Vec<[f32; 2]> = enemies
+        .iter()
+        .map(|(_, enemy)| projection.position(enemy))
+        .collect();
+    points.push(party);
+    let mut view = MapView::default();
+    view.fit(&points, [DIAGRAM_WIDTH as f32, PLOT_HEIGHT], PLOT_MARGIN);
+    let to_image = |point: [f32; 2]| {
+        let [x, y] = view.to_screen(point);
+        [x + DIAGRAM_WIDTH as f32 / 2.0, y + PLOT_HEIGHT / 2.0]
+    };
+
+    let mut shapes = Vec::new();
+    let min = view.to_battlefield([-(DIAGRAM_WIDTH as f32) / 2.0, -PLOT_HEIGHT / 2.0]);
+    let max = view.to_battlefield([DIAGRAM_WIDTH as f32 / 2.0, PLOT_HEIGHT / 2.0]);
+    let lines = |min: f32, max: f32| {
+        ((min / GRID_SIZE).ceil() as i32..=(max / GRID_SIZE).floor() as i32)
+            .map(|i| i as f32 * GRID_SIZE)
+    };
+    for x in lines(min[0], max[0]) {
+        shapes.push(Shape::Line {
+            from: to_image([x, min[1]]),
+            to: to_image([x, max[1]]),
+            width: 1.0,
+            color: if x == 0.0 { AXIS_COLOR } else { GRID_COLOR },
+        });
+    }
+    for z in lines(min[1], max[1]) {
+        shapes.push(Shape::Line {
+            from: to_image([min[0], z]),
+            to: to_image([max[0], z]),
+            width: 1.0,
+            color: if z == 0.0 { AXIS_COLOR } else { GRID_COLOR },
+        });
+    }
+
+    let party = to_image(party);
+    shapes.push(Shape::Circle {
+        center: party,
+        radius: MARKER_RADIUS,
+        fill: Some(PARTY_COLOR),
+        stroke: None,
+    });
+    shapes.push(Shape::Text {
+        position: [party[0], party[1] + MARKER_RADIUS + FONT_SIZE],
+        size: FONT_SIZE,
+        color: TEXT_COLOR,
+        text: "Party".to_string(),
+        centered: true,
+    });
+
+    for (slot, enemy) in enemies {
+        let center = to_image(projection.position(enemy));
+        let (fill, color) = match SlotState::of(enemy) {
+            SlotState::Hidden => (None, ENABLED_COLOR),
+            SlotState::NotLoaded => (Some(NOT_LOADED_COLOR), NOT_LOADED_COLOR),
+            _ => (Some(ENABLED_COLOR), ENABLED_COLOR),
+        };
+        shapes.push(Shape::Circle {
+            center,
+            radius: MARKER_RADIUS,
+            fill,
+            stroke: Some((2.0, color)),
+        });
+        shapes.push(Shape::Text {
+            position: [center[0], center[1] + FONT_SIZE / 3.0],
+            size: FONT_SIZE,
+            color: if fill.is_some() {
+                BACKGROUND_COLOR
+            } else {
+                TEXT_COLOR
+            },
+            text: slot.to_string(),
+            centered: true,
+        });
+        let name = ENEMY_NAMES
+            .get(enemy.id as usize)
+            .unwrap_or(&"Invalid enemy");
+        let untargetable = if enemy.untargetable {
+            ", untargetable"
+        } else {
+            ""
+        };
+        shapes.push(Shape::Text {
+            position: [center[0], center[1] + MARKER_RADIUS + FONT_SIZE],
+            size: FONT_SIZE,
+            color: TEXT_COLOR,
+            text: format!("{name} (Lv {}{untargetable})", enemy.level),
+            centered: true,
+        });
+    }
+
+    shapes.push(Shape::Line {
+        from: [0.0, PLOT_HEIGHT],
+        to: [DIAGRAM_WIDTH as f32, PLOT_HEIGHT],
+        width: 1.0,
+        color: AXIS_COLOR,
+    });
+    let stage_name = STAGE_NAMES
+        .get(battle_structure.stage_id as usize)
+        .unwrap_or(&"Unknown stage");
+    let flags: Vec<&str> = BATTLE_FLAG_NAMES
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| battle_structure.packed_battle_flags() & (1 << i) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    let title = format!(
+        "Encounter {index}: {stage_name} (stage {})",
+        battle_structure.stage_id
+    );
+    let mut y = PLOT_HEIGHT + 10.0 + TITLE_SIZE + 6.0;
+    shapes.push(Shape::Text {
+        position: [12.0, y],
+        size: TITLE_SIZE,
+        color: TEXT_COLOR,
+        text: title,
+        centered: false,
+    });
+
+    // one flag per cell, so that the legend fits even with all the flags set
+    y += FONT_SIZE + 6.0;
+    shapes.push(Shape::Text {
+        position: [12.0, y],
+        size: FONT_SIZE,
+        color: TEXT_COLOR,
+        text: match flags.is_empty() {
+            true => "Flags: none".to_string(),
+            false => "Flags:".to_string(),
+        },
+        centered: false,
+    });
+    for (row, names) in flags.chunks(FLAG_COLUMNS).enumerate() {
+        for (column, name) in names.iter().enumerate() {
+            shapes.push(Shape::Text {
+                position: [
+                    FLAG_OFFSET + column as f32 * FLAG_COLUMN_WIDTH,
+                    y + row as f32 * (FONT_SIZE + 6.0),
+                ],
+                size: FONT_SIZE,
+                color: TEXT_COLOR,
+                text: name.to_string(),
+                centered: false,
+            });
+        }
+    }
+    let rows = flags.len().div_ceil(FLAG_COLUMNS).max(1);
+    y += (rows - 1) as f32 * (FONT_SIZE + 6.0);
+
+    y += FONT_SIZE + 12.0;
+    let key: [(&str, Option<Rgb>, Rgb); 4] = [
+        ("Enabled", Some(ENABLED_COLOR), ENABLED_COLOR),
+        ("Hidden", None, ENABLED_COLOR),
+        ("Not loaded", Some(NOT_LOADED_COLOR), NOT_LOADED_COLOR),
+        ("Party", Some(PARTY_COLOR), PARTY_COLOR),
+    ];
+    for (i, (text, fill, color)) in key.into_iter().enumerate() {
+        let x = 20.0 + i as f32 * 120.0;
+        shapes.push(Shape::Circle {
+            center: [x, y - FONT_SIZE / 3.0],
+            radius: 6.0,
+            fill,
+            stroke: Some((2.0, color)),
+        });
+        shapes.push(Shape::Text {
+            position: [x + 12.0, y],
+            size: FONT_SIZE,
+            color: TEXT_COLOR,
+            text: text.to_string(),
+            centered: false,
+        });
+    }
+    shapes
+}
+
+/// Font of the text of the PNG diagrams, the default one of the editor. Load it once for all
+/// the diagrams rendered.
+pub fn diagram_font() -> anyhow::Result<FontArc> {
+    let font_data = egui::FontDefinitions::default()
+        .font_data
+        .remove("Ubuntu-Light")
+        .ok_or_else(|| anyhow::anyhow!("Default font not found"))?;
+    Ok(FontArc::try_from_vec(font_data.font.into_owned())?)
+}
+
+/// Renders the diagram of the encounter in the format
+pub fn render_diagram(
+    index: usize,
+    battle_structure: &BattleStructure,
+    format: DiagramFormat,
+    font: &FontArc,
+) -> anyhow::Result<Vec<u8>> {
+    let shapes = diagram_shapes(index, battle_structure);
+    match format {
+        DiagramFormat::Png => render_png(&shapes, font),
+        DiagramFormat::Svg => Ok(render_svg(&shapes).into_bytes()),
+    }
+}
+
+/// Path of the diagram of the encounter in the folder, e.g. `encounter_0120.png`
+pub fn diagram_path(folder: &Path, index: usize, format: DiagramFormat) -> PathBuf {
+    folder.join(format!("encounter_{index:04}.{}", format.extension()))
+}
+
+/// Writes the diagrams of the encounters to their `diagram_path` in the folder, replacing the
+/// existing ones, and returns their paths
+pub fn export_diagrams(
+    battle_structures: &[BattleStructure],
+    indexes: &[usize],
+    folder: &Path,
+    format: DiagramFormat,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let font = diagram_font()?;
+    indexes
+        .iter()
+        .map(|index| {
+            let battle_structure = battle_structures
+                .get(*index)
+                .ok_or_else(|| anyhow::anyhow!("Encounter {index} not found"))?;
+            let path = diagram_path(folder, *index, format);
+            write_atomic(
+                &path,
+                &render_diagram(*index, battle_structure, format, &font)?,
+            )?;
+            Ok(path)
+        })
+        .collect()
+}
+
+pub fn render_svg(shapes: &[Shape]) -> String {
+    let color = |[r, g, b]: Rgb| format!("rgb({r},{g},{b})");
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{DIAGRAM_WIDTH}\" \
+         height=\"{DIAGRAM_HEIGHT}\" font-family=\"sans-serif\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        color(BACKGROUND_COLOR)
+    );
+    for shape in shapes {
+        let element = match shape {
+            Shape::Line {
+                from,
+                to,
+                width,
+                color: line_color,
+            } => format!(
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" \
+                 stroke-width=\"{width}\"/>",
+                from[0],
+                from[1],
+                to[0],
+                to[1],
+                color(*line_color)
+            ),
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{radius}\" fill=\"{}\" stroke=\"{}\" \
+                 stroke-width=\"{}\"/>",
+                center[0],
+                center[1],
+                fill.map(color).unwrap_or("none".to_string()),
+                stroke
+                    .map(|(_, stroke)| color(stroke))
+                    .unwrap_or("none".to_string()),
+                stroke.map(|(width, _)| width).unwrap_or(0.0)
+            ),
+            Shape::Text {
+                position,
+                size,
+                color: text_color,
+                text,
+                centered,
+            } => format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{size}\" fill=\"{}\"{}>{}</text>",
+                position[0],
+                position[1],
+                color(*text_color),
+                if *centered {
+                    " text-anchor=\"middle\""
+                } else {
+                    ""
+                },
+                escape_xml(text)
+            ),
+        };
+        svg.push_str(&element);
+        svg.push('\n');
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Rasterizes the shapes on the CPU, the text using the font
+pub fn render_png(shapes: &[Shape], font: &FontArc) -> anyhow::Result<Vec<u8>> {
+    let mut pixmap = tiny_skia::Pixmap::new(DIAGRAM_WIDTH, DIAGRAM_HEIGHT)
+        .ok_or_else(|| anyhow::anyhow!("Invalid diagram size"))?;
+    pixmap.fill(skia_color(BACKGROUND_COLOR));
+
+    let paint = |color: Rgb| {
+        let mut paint = tiny_skia::Paint::default();
+        paint.set_color(skia_color(color));
+        paint.anti_alias = true;
+        paint
+    };
+    let transform = tiny_skia::Transform::identity();
+    for shape in shapes {
+        match shape {
+            Shape::Line {
+                from,
+                to,
+                width,
+                color,
+            } => {
+                let mut path = tiny_skia::PathBuilder::new();
+                path.move_to(from[0], from[1]);
+                path.line_to(to[0], to[1]);
+                if let Some(path) = path.finish() {
+                    let stroke = tiny_skia::Stroke {
+                        width: *width,
+                        ..Default::default()
+                    };
+                    pixmap.stroke_path(&path, &paint(*color), &stroke, transform, None);
+                }
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                let Some(path) = tiny_skia::PathBuilder::from_circle(center[0], center[1], *radius)
+                else {
+                    continue;
+                };
+                if let Some(fill) = fill {
+                    let rule = tiny_skia::FillRule::Winding;
+                    pixmap.fill_path(&path, &paint(*fill), rule, transform, None);
+                }
+                if let Some((width, color)) = stroke {
+                    let stroke = tiny_skia::Stroke {
+                        width: *width,
+                        ..Default::default()
+                    };
+                    pixmap.stroke_path(&path, &paint(*color), &stroke, transform, None);
+                }
+            }
+            Shape::Text {
+                position,
+                size,
+                color,
+                text,
+                centered,
+            } => draw_text(&mut pixmap, font, *position, *size, *color, text, *centered),
+        }
+    }
+    Ok(pixmap.encode_png()?)
+}
+
+fn skia_color([r, g, b]: Rgb) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba8(r, g, b, 255)
+}
+
+fn draw_text(
+    pixmap: &mut tiny_skia::Pixmap,
+    font: &FontArc,
+    position: [f32; 2],
+    size: f32,
+    color: Rgb,
+    text: &str,
+    centered: bool,
+) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let glyphs: Vec<_> = text.chars().map(|c| scaled.glyph_id(c)).collect();
+    let width: f32 = glyphs.iter().map(|id| scaled.h_advance(*id)).sum();
+    let mut x = if centered {
+        position[0] - width / 2.0
+    } else {
+        position[0]
+    };
+
+    let (pixmap_width, pixmap_height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let pixels = pixmap.pixels_mut();
+    for id in glyphs {
+        let glyph = id.with_scale_and_position(size, ab_glyph::point(x, position[1]));
+        x += scaled.h_advance(id);
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|glyph_x, glyph_y, coverage| {
+            let px = bounds.min.x as i32 + glyph_x as i32;
+            let py = bounds.min.y as i32 + glyph_y as i32;
+            if px < 0 || py < 0 || px >= pixmap_width || py >= pixmap_height {
+                return;
+            }
+            // the diagram is opaque, so the pixels are blended without their alpha
+            let pixel = &mut pixels[(py * pixmap_width + px) as usize];
+            let coverage = coverage.clamp(0.0, 1.0);
+            let blend = |dest: u8, src: u8| {
+                (dest as f32 * (1.0 - coverage) + src as f32 * coverage).round() as u8
+            };
+            if let Some(blended) = tiny_skia::PremultipliedColorU8::from_rgba(
+                blend(pixel.red(), color[0]),
+                blend(pixel.green(), color[1]),
+                blend(pixel.blue(), color[2]),
+                255,
+            ) {
+                *pixel = blended;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::{sample_battle_structure, BattleFlags};
+
+    use super::*;
+
+    #[test]
+    fn test_render_diagram() {
+        let battle_structure = sample_battle_structure();
+
+        let font = diagram_font().unwrap();
+        let svg = render_diagram(3, &battle_structure, DiagramFormat::Svg, &font).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(&format!(
+            ">Encounter 3: {} (stage 6)</text>",
+            escape_xml(STAGE_NAMES[6])
+        )));
+        assert!(svg.contains(">0</text>"));
+        assert!(!svg.contains(">1</text>"));
+
+        let png = render_diagram(3, &battle_structure, DiagramFormat::Png, &font).unwrap();
+        let pixmap = tiny_skia::Pixmap::decode_png(&png).unwrap();
+        assert_eq!(
+            (pixmap.width(), pixmap.height()),
+            (DIAGRAM_WIDTH, DIAGRAM_HEIGHT)
+        );
+        assert!(pixmap
+            .pixels()
+            .iter()
+            .any(|pixel| pixel.red() < 100 && pixel.green() < 100 && pixel.blue() < 100));
+    }
+
+    #[test]
+    fn test_legend_fits_all_flags() {
+        let mut battle_structure = sample_battle_structure();
+        battle_structure.flags = BattleFlags {
+            cannot_escape: true,
+            disable_win_fanfare: true,
+            show_timer: true,
+            no_exp: true,
+            disable_exp_screen: true,
+            force_surprise_attack: true,
+            force_back_attack: true,
+            scripted_battle: true,
+        };
+        let shapes = diagram_shapes(3, &battle_structure);
+        let flags: Vec<_> = shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Text { position, text, .. } => Some((position, text)),
+                _ => None,
+            })
+            .filter(|(_, text)| BATTLE_FLAG_NAMES.contains(&text.as_str()))
+            .collect();
+        assert_eq!(flags.len(), BATTLE_FLAG_NAMES.len());
+        assert!(flags.iter().all(|(position, _)| {
+            position[0] + FLAG_COLUMN_WIDTH <= DIAGRAM_WIDTH as f32
+                && position[1] < DIAGRAM_HEIGHT as f32 - FONT_SIZE
+        }));
+    }
+
+    #[test]
+    fn test_export_diagrams() {
+        let battle_structure = sample_battle_structure();
+        let battle_structures = vec![battle_structure; 3];
+        let folder = tempfile::tempdir().unwrap();
+
+        let paths = export_diagrams(
+            &battle_structures,
+            &[0, 2],
+            folder.path(),
+            DiagramFormat::Svg,
+        )
+        .unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                folder.path().join("encounter_0000.svg"),
+                folder.path().join("encounter_0002.svg"),
+            ]
+        );
+        assert!(paths.iter().all(|path| path.exists()));
+        export_diagrams(&battle_structures, &[0], folder.path(), DiagramFormat::Svg).unwrap();
+        assert_eq!(folder.path().read_dir().unwrap().count(), 2);
+        assert!(
+            export_diagrams(&battle_structures, &[3], folder.path(), DiagramFormat::Png).is_err()
+        );
+    }
+}
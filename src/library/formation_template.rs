@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use super::battle_structure::{BattleStructure, Coordinate};
+
+/// Named enemy positions (x, y, z), applied to the enemy slots in order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormationTemplate {
+    pub name: String,
+    pub positions: Vec<[i16; 3]>,
+}
+
+/// Templates shipped with the editor, placed in front of the party
+pub fn builtin_templates() -> Vec<FormationTemplate> {
+    let template = |name: &str, positions: &[[i16; 3]]| FormationTemplate {
+        name: name.to_string(),
+        positions: positions.to_vec(),
+    };
+    vec![
+        template("Single", &[[0, 0, -4000]]),
+        template("Pair", &[[-1000, 0, -4000], [1000, 0, -4000]]),
+        template(
+            "Line of three",
+            &[[-1800, 0, -4000], [0, 0, -4000], [1800, 0, -4000]],
+        ),
+        template(
+            "Line of four",
+            &[
+                [-2700, 0, -4000],
+                [-900, 0, -4000],
+                [900, 0, -4000],
+                [2700, 0, -4000],
+            ],
+        ),
+        template(
+            "Boss with two flankers",
+            &[[0, 0, -5000], [-2200, 0, -3500], [2200, 0, -3500]],
+        ),
+        template(
+            "Wedge",
+            &[[0, 0, -3200], [-1500, 0, -4500], [1500, 0, -4500]],
+        ),
+        template(
+            "Two rows",
+            &[
+                [-1500, 0, -3200],
+                [0, 0, -3200],
+                [1500, 0, -3200],
+                [-1500, 0, -5200],
+                [0, 0, -5200],
+                [1500, 0, -5200],
+            ],
+        ),
+    ]
+}
+
+impl FormationTemplate {
+    /// Captures the positions of the enabled enemies of the battle structure
+    pub fn from_battle_structure(name: &str, battle_structure: &BattleStructure) -> Self {
+        FormationTemplate {
+            name: name.to_string(),
+            positions: battle_structure
+                .enemies
+                .iter()
+                .filter(|enemy| enemy.enabled)
+                .map(|enemy| {
+                    let coordinate = &enemy.coordinate;
+                    [coordinate.x, coordinate.y, coordinate.z]
+                })
+                .collect(),
+        }
+    }
+
+    /// Battle structure with the positions applied to the enabled slots first, then to the
+    /// disabled ones. Enemy ids, levels and flags are kept.
+    pub fn apply(&self, battle_structure: &BattleStructure) -> BattleStructure {
+        let mut battle_structure = battle_structure.clone();
+        let enemies = &battle_structure.enemies;
+        let mut slots: Vec<usize> = (0..enemies.len()).collect();
+        slots.sort_by_key(|slot| !enemies[*slot].enabled);
+
+        for (slot, [x, y, z]) in slots.into_iter().zip(self.positions.iter().copied()) {
+            battle_structure.enemies[slot].coordinate = Coordinate { x, y, z };
+        }
+        battle_structure
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    #[test]
+    fn test_apply_template() {
+        let mut battle_structure = sample_battle_structure();
+        battle_structure.enemies[2].enabled = true;
+
+        let template = builtin_templates()
+            .into_iter()
+            .find(|template| template.name == "Line of three")
+            .unwrap();
+        let applied = template.apply(&battle_structure);
+        assert_eq!(applied.enemies[0].coordinate.x, -1800);
+        assert_eq!(applied.enemies[2].coordinate.x, 0);
+        assert_eq!(applied.enemies[1].coordinate.x, 1800);
+        assert_eq!(applied.enemies[3], battle_structure.enemies[3]);
+        for (applied, original) in applied.enemies.iter().zip(&battle_structure.enemies) {
+            assert_eq!(applied.id, original.id);
+            assert_eq!(applied.level, original.level);
+            assert_eq!(applied.enabled, original.enabled);
+        }
+    }
+
+    #[test]
+    fn test_capture_template() {
+        let battle_structure = sample_battle_structure();
+        let template = FormationTemplate::from_battle_structure("Mine", &battle_structure);
+        assert_eq!(template.positions, vec![[1100, 0, -3300]]);
+
+        let mut moved = battle_structure.clone();
+        moved.enemies[0].coordinate.x = 0;
+        assert_eq!(template.apply(&moved), battle_structure);
+    }
+}
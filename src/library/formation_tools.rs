@@ -0,0 +1,166 @@
+use super::{battle_structure::BattleStructure, enemy_slots::SlotSelection};
+
+/// Operation moving the selected enemy slots of an encounter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormationTool {
+    /// Spaces the slots evenly along X around their current center, keeping their X order
+    DistributeX { spacing: i16 },
+    /// Moves the slots so that the center of their X/Z extent is at the origin
+    Center,
+    /// Mirrors the slots across the Z axis
+    Mirror,
+    /// Scales the X/Z distances of the slots from their center
+    Scale { factor: f32 },
+    /// Moves every slot by the offset (x, y, z)
+    Nudge { offset: [i16; 3] },
+}
+
+impl FormationTool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FormationTool::DistributeX { .. } => "Distribute along X",
+            FormationTool::Center => "Center on origin",
+            FormationTool::Mirror => "Mirror across Z",
+            FormationTool::Scale { .. } => "Scale spread",
+            FormationTool::Nudge { .. } => "Nudge",
+        }
+    }
+
+    /// Moves the selected enemy slots, returning the number of slots moved. Coordinates are
+    /// clamped to the range of the game.
+    pub fn apply(&self, battle_structure: &mut BattleStructure, selection: SlotSelection) -> usize {
+        let slots: Vec<usize> = (0..battle_structure.enemies.len())
+            .filter(|slot| selection.contains(*slot, &battle_structure.enemies[*slot]))
+            .collect();
+        let positions: Vec<[i32; 3]> = slots
+            .iter()
+            .map(|slot| {
+                let coordinate = &battle_structure.enemies[*slot].coordinate;
+                [
+                    coordinate.x as i32,
+                    coordinate.y as i32,
+                    coordinate.z as i32,
+                ]
+            })
+            .collect();
+        let Some(center) = center(&positions) else {
+            return 0;
+        };
+
+        let moved: Vec<[i32; 3]> = match *self {
+            FormationTool::DistributeX { spacing } => {
+                let mut order: Vec<usize> = (0..positions.len()).collect();
+                order.sort_by_key(|i| positions[*i][0]);
+                let start = center[0] - spacing as i32 * (positions.len() as i32 - 1) / 2;
+                let mut moved = positions.clone();
+                for (rank, i) in order.into_iter().enumerate() {
+                    moved[i][0] = start + spacing as i32 * rank as i32;
+                }
+                moved
+            }
+            FormationTool::Center => positions
+                .iter()
+                .map(|[x, y, z]| [x - center[0], *y, z - center[1]])
+                .collect(),
+            FormationTool::Mirror => positions.iter().map(|[x, y, z]| [-x, *y, *z]).collect(),
+            FormationTool::Scale { factor } => {
+                let scale = |value: i32, center: i32| {
+                    center + ((value - center) as f32 * factor).round() as i32
+                };
+                positions
+                    .iter()
+                    .map(|[x, y, z]| [scale(*x, center[0]), *y, scale(*z, center[1])])
+                    .collect()
+            }
+            FormationTool::Nudge {
+                offset: [dx, dy, dz],
+            } => positions
+                .iter()
+                .map(|[x, y, z]| [x + dx as i32, y + dy as i32, z + dz as i32])
+                .collect(),
+        };
+
+        for (slot, [x, y, z]) in slots.iter().zip(moved) {
+            let coordinate = &mut battle_structure.enemies[*slot].coordinate;
+            coordinate.x = clamp(x);
+            coordinate.y = clamp(y);
+            coordinate.z = clamp(z);
+        }
+        slots.len()
+    }
+}
+
+/// Center of the X/Z extent of the positions, as (x, z)
+fn center(positions: &[[i32; 3]]) -> Option<[i32; 2]> {
+    let min_x = positions.iter().map(|position| position[0]).min()?;
+    let max_x = positions.iter().map(|position| position[0]).max()?;
+    let min_z = positions.iter().map(|position| position[2]).min()?;
+    let max_z = positions.iter().map(|position| position[2]).max()?;
+    Some([(min_x + max_x) / 2, (min_z + max_z) / 2])
+}
+
+fn clamp(value: i32) -> i16 {
+    value.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    fn xz(battle_structure: &BattleStructure, slot: usize) -> [i16; 2] {
+        let coordinate = &battle_structure.enemies[slot].coordinate;
+        [coordinate.x, coordinate.z]
+    }
+
+    #[test]
+    fn test_formation_tools() {
+        let mut battle_structure = sample_battle_structure();
+        battle_structure.enemies[1].enabled = true;
+        battle_structure.enemies[1].coordinate.x = 100;
+        battle_structure.enemies[1].coordinate.z = -5300;
+        battle_structure.enemies[1].coordinate.y = -200;
+        let original = battle_structure.clone();
+
+        let mirror = FormationTool::Mirror;
+        assert_eq!(
+            mirror.apply(&mut battle_structure, SlotSelection::Enabled),
+            2
+        );
+        assert_eq!(xz(&battle_structure, 0), [-1100, -3300]);
+        assert_eq!(xz(&battle_structure, 1), [-100, -5300]);
+        assert_eq!(battle_structure.enemies[2], original.enemies[2]);
+        mirror.apply(&mut battle_structure, SlotSelection::Enabled);
+        assert_eq!(battle_structure, original);
+
+        FormationTool::Center.apply(&mut battle_structure, SlotSelection::Enabled);
+        assert_eq!(xz(&battle_structure, 0), [500, 1000]);
+        assert_eq!(xz(&battle_structure, 1), [-500, -1000]);
+        assert_eq!(battle_structure.enemies[1].coordinate.y, -200);
+
+        FormationTool::Scale { factor: 2.0 }.apply(&mut battle_structure, SlotSelection::Enabled);
+        assert_eq!(xz(&battle_structure, 0), [1000, 2000]);
+        assert_eq!(xz(&battle_structure, 1), [-1000, -2000]);
+
+        FormationTool::DistributeX { spacing: 600 }
+            .apply(&mut battle_structure, SlotSelection::Enabled);
+        assert_eq!(xz(&battle_structure, 0), [300, 2000]);
+        assert_eq!(xz(&battle_structure, 1), [-300, -2000]);
+
+        let nudge = FormationTool::Nudge {
+            offset: [0, i16::MIN, 10],
+        };
+        let mut selected = battle_structure.clone();
+        assert_eq!(nudge.apply(&mut selected, SlotSelection::Selected(2)), 1);
+        assert_eq!(selected.enemies[2].coordinate.z, original.enemies[2].coordinate.z + 10);
+        assert_eq!(selected.enemies[3], battle_structure.enemies[3]);
+
+        assert_eq!(nudge.apply(&mut battle_structure, SlotSelection::All), 8);
+        assert_eq!(battle_structure.enemies[1].coordinate.y, i16::MIN);
+        assert_eq!(
+            battle_structure.enemies[7].coordinate.z,
+            original.enemies[7].coordinate.z + 10
+        );
+    }
+}
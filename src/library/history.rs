@@ -0,0 +1,315 @@
+use super::battle_structure::{BattleStructure, FieldChange};
+
+/// Maximum number of commands kept in the undo stack
+const MAX_HISTORY_LENGTH: usize = 1000;
+
+/// State of a battle structure before and after an edit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub index: usize,
+    pub before: BattleStructure,
+    pub after: BattleStructure,
+}
+
+impl Edit {
+    pub fn changed_fields(&self) -> Vec<FieldChange> {
+        self.before.changed_fields(&self.after)
+    }
+}
+
+/// Undoable change of one or more battle structures
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    pub label: String,
+    pub edits: Vec<Edit>,
+}
+
+impl Command {
+    /// Command made of a single edit, labelled with the changed fields
+    pub fn from_edit(edit: Edit) -> Command {
+        let fields: Vec<String> = edit
+            .changed_fields()
+            .into_iter()
+            .map(|field| field.name)
+            .collect();
+        Command {
+            label: format!("Encounter {}: {}", edit.index, fields.join(", ")),
+            edits: vec![edit],
+        }
+    }
+
+    /// Command that sets the battle structures at the given indexes, skipping the unchanged ones
+    pub fn from_changes(
+        label: &str,
+        battle_structure_list: &[BattleStructure],
+        changes: impl IntoIterator<Item = (usize, BattleStructure)>,
+    ) -> Command {
+        Command {
+            label: label.to_string(),
+            edits: changes
+                .into_iter()
+                .filter_map(|(index, after)| {
+                    let before = battle_structure_list.get(index)?;
+                    (*before != after).then(|| Edit {
+                        index,
+                        before: before.clone(),
+                        after,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub fn apply(&self, battle_structure_list: &mut [BattleStructure]) {
+        for edit in &self.edits {
+            if let Some(battle_structure) = battle_structure_list.get_mut(edit.index) {
+                *battle_structure = edit.after.clone();
+            }
+        }
+    }
+
+    pub fn revert(&self, battle_structure_list: &mut [BattleStructure]) {
+        for edit in self.edits.iter().rev() {
+            if let Some(battle_structure) = battle_structure_list.get_mut(edit.index) {
+                *battle_structure = edit.before.clone();
+            }
+        }
+    }
+
+    /// Whether `edit` continues this command, e.g. the next frame of a slider drag or of an
+    /// enemy dragged on the battlefield, which changes some of the same fields
+    fn can_merge(&self, edit: &Edit) -> bool {
+        match self.edits.as_slice() {
+            [last] => {
+                let last_fields = last.changed_fields();
+                let last_fields = field_names(&last_fields);
+                last.index == edit.index
+                    && last.after == edit.before
+                    && field_names(&edit.changed_fields())
+                        .iter()
+                        .any(|name| last_fields.contains(name))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Undo and redo stacks of the commands applied to the battle structures
+#[derive(Debug, Default)]
+pub struct History {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+}
+
+impl History {
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    /// Records a command already applied to the battle structures
+    pub fn push(&mut self, command: Command) {
+        if command.is_empty() {
+            return;
+        }
+        self.redo_stack.clear();
+        self.undo_stack.push(command);
+        if self.undo_stack.len() > MAX_HISTORY_LENGTH {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Records an edit already applied, merging it into the last command when `merge` is set
+    /// and the same fields of the same battle structure are being changed
+    pub fn push_edit(&mut self, edit: Edit, merge: bool) {
+        if edit.before == edit.after {
+            return;
+        }
+        if merge {
+            if let Some(last) = self.undo_stack.last_mut() {
+                if last.can_merge(&edit) {
+                    let before = last.edits[0].before.clone();
+                    *last = Command::from_edit(Edit {
+                        index: edit.index,
+                        before,
+                        after: edit.after,
+                    });
+                    if last.edits[0].before == last.edits[0].after {
+                        self.undo_stack.pop();
+                    }
+                    self.redo_stack.clear();
+                    return;
+                }
+            }
+        }
+        self.push(Command::from_edit(edit));
+    }
+
+    /// Applies a command and records it
+    pub fn execute(&mut self, command: Command, battle_structure_list: &mut [BattleStructure]) {
+        command.apply(battle_structure_list);
+        self.push(command);
+    }
+
+    pub fn undo(&mut self, battle_structure_list: &mut [BattleStructure]) -> Option<&Command> {
+        let command = self.undo_stack.pop()?;
+        command.revert(battle_structure_list);
+        self.redo_stack.push(command);
+        self.redo_stack.last()
+    }
+
+    pub fn redo(&mut self, battle_structure_list: &mut [BattleStructure]) -> Option<&Command> {
+        let command = self.redo_stack.pop()?;
+        command.apply(battle_structure_list);
+        self.undo_stack.push(command);
+        self.undo_stack.last()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Applied commands, from the oldest to the newest
+    pub fn undo_commands(&self) -> &[Command] {
+        &self.undo_stack
+    }
+
+    /// Undone commands, from the next one to redo to the last one
+    pub fn redo_commands(&self) -> impl Iterator<Item = &Command> {
+        self.redo_stack.iter().rev()
+    }
+}
+
+fn field_names(fields: &[FieldChange]) -> Vec<&str> {
+    fields.iter().map(|field| field.name.as_str()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    fn battle_structure_list() -> Vec<BattleStructure> {
+        vec![sample_battle_structure(); 4]
+    }
+
+    fn edit_x(list: &mut [BattleStructure], index: usize, x: i16) -> Edit {
+        let before = list[index].clone();
+        list[index].enemies[0].coordinate.x = x;
+        Edit {
+            index,
+            before,
+            after: list[index].clone(),
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut list = battle_structure_list();
+        let original = list.clone();
+        let mut history = History::default();
+
+        let edit = edit_x(&mut list, 1, 10);
+        history.push_edit(edit, false);
+        let edited = list.clone();
+        assert_eq!(history.undo_commands()[0].label, "Encounter 1: enemy 0 x");
+
+        assert!(history.undo(&mut list).is_some());
+        assert_eq!(list, original);
+        assert!(history.can_redo());
+
+        assert!(history.redo(&mut list).is_some());
+        assert_eq!(list, edited);
+        assert!(!history.can_redo());
+        assert!(history.redo(&mut list).is_none());
+    }
+
+    #[test]
+    fn test_merge_continuous_edits() {
+        let mut list = battle_structure_list();
+        let original = list.clone();
+        let mut history = History::default();
+
+        for x in [10, 20, 30] {
+            let edit = edit_x(&mut list, 2, x);
+            history.push_edit(edit, true);
+        }
+        assert_eq!(history.undo_commands().len(), 1);
+        let fields = history.undo_commands()[0].edits[0].changed_fields();
+        assert_eq!(fields[0].old, "1100");
+        assert_eq!(fields[0].new, "30");
+
+        let edit = edit_x(&mut list, 2, 40);
+        history.push_edit(edit, false);
+        assert_eq!(history.undo_commands().len(), 2);
+
+        let mut after = list[2].clone();
+        after.enemies[0].coordinate.x = 50;
+        after.enemies[0].coordinate.z = 50;
+        let edit = Edit {
+            index: 2,
+            before: std::mem::replace(&mut list[2], after.clone()),
+            after,
+        };
+        history.push_edit(edit, true);
+        assert_eq!(history.undo_commands().len(), 2);
+
+        let mut after = list[2].clone();
+        after.stage_id = 1;
+        let edit = Edit {
+            index: 2,
+            before: std::mem::replace(&mut list[2], after.clone()),
+            after,
+        };
+        history.push_edit(edit, true);
+        assert_eq!(history.undo_commands().len(), 3);
+
+        history.undo(&mut list);
+
+        history.undo(&mut list);
+        history.undo(&mut list);
+        assert_eq!(list, original);
+    }
+
+    #[test]
+    fn test_merge_back_to_original_removes_command() {
+        let mut list = battle_structure_list();
+        let mut history = History::default();
+
+        let edit = edit_x(&mut list, 0, 10);
+        history.push_edit(edit, true);
+        let edit = edit_x(&mut list, 0, 1100);
+        history.push_edit(edit, true);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_execute_command() {
+        let mut list = battle_structure_list();
+        let original = list.clone();
+        let mut history = History::default();
+
+        let mut changed = list[3].clone();
+        changed.stage_id = 12;
+        let command = Command::from_changes(
+            "Paste",
+            &list,
+            [(3, changed.clone()), (1, list[1].clone())],
+        );
+        assert_eq!(command.edits.len(), 1);
+
+        history.execute(command, &mut list);
+        assert_eq!(list[3], changed);
+        history.undo(&mut list);
+        assert_eq!(list, original);
+    }
+}
@@ -0,0 +1,345 @@
+use std::path::{Path, PathBuf};
+
+/// Folder names of FFVIII inside `steamapps/common`
+const STEAM_FOLDER_NAMES: [&str; 2] = ["FINAL FANTASY VIII", "FINAL FANTASY VIII Remastered"];
+
+/// Steam roots relative to the home folder (native, symlinked, flatpak and snap installs)
+const STEAM_ROOTS: [&str; 5] = [
+    ".steam/steam",
+    ".steam/root",
+    ".local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    "snap/steam/common/.local/share/Steam",
+];
+
+/// Install folders of non-Steam releases inside a Wine/Proton `drive_c`
+const PREFIX_INSTALL_FOLDERS: [&str; 3] = [
+    "Program Files (x86)/Squaresoft/Final Fantasy VIII",
+    "Program Files/Squaresoft/Final Fantasy VIII",
+    "Program Files (x86)/Steam/steamapps/common/FINAL FANTASY VIII",
+];
+
+/// Files containing the battle data inside a language folder
+const BATTLE_FILE_NAMES: [&str; 4] = ["scene.out", "battle.fs", "battle.fi", "battle.fl"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallationKind {
+    /// Installed through a Steam library
+    Steam,
+    /// Installed inside a Proton prefix of Steam
+    ProtonPrefix,
+}
+
+/// A FFVIII installation found on disk
+#[derive(Debug, Clone)]
+pub struct Installation {
+    pub root: PathBuf,
+    pub kind: InstallationKind,
+    pub languages: Vec<LanguageFolder>,
+}
+
+/// Language folder (e.g. `Data/lang-en`) and the battle data files found in it
+#[derive(Debug, Clone)]
+pub struct LanguageFolder {
+    pub code: String,
+    pub battle_files: Vec<PathBuf>,
+}
+
+impl Installation {
+    fn try_from_root(root: &Path, kind: InstallationKind) -> Option<Installation> {
+        let data_folder = find_child_ignore_case(root, "Data")?;
+        let languages = language_folders(&data_folder);
+        if languages.is_empty() {
+            return None;
+        }
+
+        Some(Installation {
+            root: root.to_path_buf(),
+            kind,
+            languages,
+        })
+    }
+
+    pub fn name(&self) -> String {
+        self.root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.root.display().to_string())
+    }
+}
+
+/// Scans the Steam libraries and Proton prefixes under the home folder for FFVIII installations.
+/// The Steam roots are the Linux ones, other platforms are not scanned.
+pub fn discover_installations(home: &Path) -> Vec<Installation> {
+    let mut installations: Vec<Installation> = Vec::new();
+
+    for library in steam_libraries(home) {
+        let steamapps = library.join("steamapps");
+
+        for folder_name in STEAM_FOLDER_NAMES {
+            let root = steamapps.join("common").join(folder_name);
+            push_unique(
+                &mut installations,
+                Installation::try_from_root(&root, InstallationKind::Steam),
+            );
+        }
+
+        let Ok(prefixes) = steamapps.join("compatdata").read_dir() else {
+            continue;
+        };
+        for prefix in prefixes.flatten() {
+            let drive_c = prefix.path().join("pfx").join("drive_c");
+            for install_folder in PREFIX_INSTALL_FOLDERS {
+                push_unique(
+                    &mut installations,
+                    Installation::try_from_root(
+                        &drive_c.join(install_folder),
+                        InstallationKind::ProtonPrefix,
+                    ),
+                );
+            }
+        }
+    }
+
+    installations
+}
+
+/// Steam library folders found in the Steam roots and in their `libraryfolders.vdf`
+pub fn steam_libraries(home: &Path) -> Vec<PathBuf> {
+    let mut libraries: Vec<PathBuf> = Vec::new();
+
+    for steam_root in STEAM_ROOTS.iter().map(|root| home.join(root)) {
+        if !steam_root.join("steamapps").is_dir() {
+            continue;
+        }
+        let vdf_path = steam_root.join("steamapps").join("libraryfolders.vdf");
+        let vdf_libraries = std::fs::read_to_string(vdf_path)
+            .map(|contents| parse_library_folders(&contents))
+            .unwrap_or_default();
+
+        for library in std::iter::once(steam_root).chain(vdf_libraries) {
+            let library = library.canonicalize().unwrap_or(library);
+            if library.is_dir() && !libraries.contains(&library) {
+                libraries.push(library);
+            }
+        }
+    }
+
+    libraries
+}
+
+/// Extracts the library paths from the contents of a `libraryfolders.vdf`
+pub fn parse_library_folders(contents: &str) -> Vec<PathBuf> {
+    let tokens = vdf_tokens(contents);
+    tokens
+        .windows(2)
+        .filter(|pair| pair[0].eq_ignore_ascii_case("path"))
+        .map(|pair| PathBuf::from(&pair[1]))
+        .collect()
+}
+
+/// Whether the path is a loose scene.out file
+pub fn is_scene_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case("scene.out"))
+}
+
+/// Language code of the `lang-xx` folder containing the path, if any
+pub fn language_from_path(path: &Path) -> Option<String> {
+    path.ancestors().find_map(|ancestor| {
+        let folder_name = ancestor.file_name()?.to_string_lossy().to_lowercase();
+        folder_name.strip_prefix("lang-").map(|code| code.to_string())
+    })
+}
+
+fn vdf_tokens(contents: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut token = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        token.push(escaped);
+                    }
+                }
+                c => token.push(c),
+            }
+        }
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn language_folders(data_folder: &Path) -> Vec<LanguageFolder> {
+    let Ok(entries) = data_folder.read_dir() else {
+        return Vec::new();
+    };
+
+    let mut languages: Vec<LanguageFolder> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let folder_name = path.file_name()?.to_string_lossy().to_lowercase();
+            let code = folder_name.strip_prefix("lang-")?.to_string();
+            let battle_files = battle_files(&path);
+            Some(LanguageFolder { code, battle_files })
+        })
+        .collect();
+    languages.sort_by(|a, b| a.code.cmp(&b.code));
+    languages
+}
+
+fn battle_files(language_folder: &Path) -> Vec<PathBuf> {
+    [language_folder.to_path_buf(), language_folder.join("battle")]
+        .iter()
+        .flat_map(|folder| {
+            BATTLE_FILE_NAMES
+                .iter()
+                .filter_map(|name| find_child_ignore_case(folder, name))
+        })
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn find_child_ignore_case(folder: &Path, name: &str) -> Option<PathBuf> {
+    folder
+        .read_dir()
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .is_some_and(|file_name| file_name.eq_ignore_ascii_case(name))
+        })
+}
+
+fn push_unique(installations: &mut Vec<Installation>, installation: Option<Installation>) {
+    let Some(installation) = installation else {
+        return;
+    };
+    let root = installation.root.canonicalize().unwrap_or(installation.root.clone());
+    if !installations
+        .iter()
+        .any(|other| other.root.canonicalize().unwrap_or(other.root.clone()) == root)
+    {
+        installations.push(installation);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+
+    const LIBRARY_FOLDERS: &str = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/squall/.local/share/Steam"
+		"label"		""
+		"apps"
+		{
+			"39150"		"1402335402"
+		}
+	}
+	"1"
+	{
+		"path"		"/mnt/games/Steam\\Library"
+		"apps"
+		{
+		}
+	}
+}
+"#;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, []).unwrap();
+    }
+
+    #[test]
+    fn test_parse_library_folders() {
+        assert_eq!(
+            parse_library_folders(LIBRARY_FOLDERS),
+            vec![
+                PathBuf::from("/home/squall/.local/share/Steam"),
+                PathBuf::from("/mnt/games/Steam\\Library"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_language_from_path() {
+        assert_eq!(
+            language_from_path(Path::new("/games/FF8/Data/Lang-FR/battle/scene.out")),
+            Some("fr".to_string())
+        );
+        assert_eq!(language_from_path(Path::new("/tmp/scene.out")), None);
+    }
+
+    #[test]
+    fn test_discover_installations() {
+        let home = tempfile::tempdir().unwrap();
+        let external = tempfile::tempdir().unwrap();
+
+        let steam_root = home.path().join(".local/share/Steam");
+        fs::create_dir_all(steam_root.join("steamapps")).unwrap();
+        fs::write(
+            steam_root.join("steamapps/libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\" {{ \"1\" {{ \"path\" \"{}\" }} }}",
+                external.path().display()
+            ),
+        )
+        .unwrap();
+
+        let steam_install = steam_root.join("steamapps/common/FINAL FANTASY VIII");
+        touch(&steam_install.join("Data/lang-en/battle.fs"));
+        touch(&steam_install.join("Data/lang-en/battle.fi"));
+        touch(&steam_install.join("Data/lang-fr/battle/scene.out"));
+
+        let proton_install = external.path().join(
+            "steamapps/compatdata/1234/pfx/drive_c/Program Files (x86)/Squaresoft/Final Fantasy VIII",
+        );
+        touch(&proton_install.join("Data/lang-it/scene.out"));
+
+        let installations = discover_installations(home.path());
+        assert_eq!(installations.len(), 2);
+
+        let steam = &installations[0];
+        assert_eq!(steam.kind, InstallationKind::Steam);
+        assert_eq!(steam.name(), "FINAL FANTASY VIII");
+        assert_eq!(
+            steam
+                .languages
+                .iter()
+                .map(|language| language.code.as_str())
+                .collect::<Vec<_>>(),
+            vec!["en", "fr"]
+        );
+        assert_eq!(steam.languages[0].battle_files.len(), 2);
+        assert!(is_scene_file(&steam.languages[1].battle_files[0]));
+
+        let proton = &installations[1];
+        assert_eq!(proton.kind, InstallationKind::ProtonPrefix);
+        assert_eq!(proton.languages[0].code, "it");
+        assert!(is_scene_file(&proton.languages[0].battle_files[0]));
+    }
+
+    #[test]
+    fn test_discover_without_steam() {
+        let home = tempfile::tempdir().unwrap();
+        assert!(discover_installations(home.path()).is_empty());
+    }
+}
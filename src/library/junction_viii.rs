@@ -0,0 +1,266 @@
+use std::{
+    fmt::Write as _,
+    io::{Cursor, Write as _},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use super::{ffnx::data_scene_path, scene_file::write_atomic, xml::escape_xml};
+
+/// Name of the metadata file read by Junction VIII
+pub const MOD_XML_FILE_NAME: &str = "mod.xml";
+
+/// Metadata of a Junction VIII mod
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModInfo {
+    pub id: String,
+    pub name: String,
+    pub author: String,
+    pub version: String,
+    pub description: String,
+    pub category: String,
+    pub preview_files: Vec<PathBuf>,
+}
+
+impl Default for ModInfo {
+    fn default() -> Self {
+        Self {
+            id: generate_mod_id(),
+            name: "Battle structure mod".to_string(),
+            author: String::new(),
+            version: "1.0".to_string(),
+            description: String::new(),
+            category: "Gameplay".to_string(),
+            preview_files: Vec::new(),
+        }
+    }
+}
+
+impl ModInfo {
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        _ = writeln!(xml, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        _ = writeln!(xml, "<ModInfo>");
+        _ = writeln!(xml, "  <ID>{}</ID>", escape_xml(&self.id));
+        _ = writeln!(xml, "  <Name>{}</Name>", escape_xml(&self.name));
+        _ = writeln!(xml, "  <Author>{}</Author>", escape_xml(&self.author));
+        _ = writeln!(xml, "  <Version>{}</Version>", escape_xml(&self.version));
+        _ = writeln!(
+            xml,
+            "  <Description>{}</Description>",
+            escape_xml(&self.description)
+        );
+        _ = writeln!(xml, "  <Category>{}</Category>", escape_xml(&self.category));
+        if let Some(preview) = self.preview_files.first().and_then(|path| path.file_name()) {
+            _ = writeln!(
+                xml,
+                "  <PreviewFile>{}</PreviewFile>",
+                escape_xml(&preview.to_string_lossy())
+            );
+        }
+        _ = writeln!(xml, "</ModInfo>");
+        xml
+    }
+
+    /// Preview files with the name they get inside the mod, which must be unique
+    fn preview_file_names(&self) -> anyhow::Result<Vec<(&Path, String)>> {
+        let mut files: Vec<(&Path, String)> = Vec::new();
+        for path in &self.preview_files {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| anyhow::anyhow!("Invalid preview file: {}", path.display()))?;
+            if name.eq_ignore_ascii_case(MOD_XML_FILE_NAME) {
+                return Err(anyhow::anyhow!(
+                    "Preview {} cannot be named {MOD_XML_FILE_NAME}",
+                    path.display()
+                ));
+            }
+            if files
+                .iter()
+                .any(|(_, other)| other.eq_ignore_ascii_case(&name))
+            {
+                return Err(anyhow::anyhow!(
+                    "Several preview files are named {name}, rename them first"
+                ));
+            }
+            files.push((path, name));
+        }
+        Ok(files)
+    }
+}
+
+/// Writes the mod as a folder ready to be imported by Junction VIII
+pub fn write_mod_folder(
+    folder: &Path,
+    info: &ModInfo,
+    language: Option<&str>,
+    scene_bytes: &[u8],
+) -> anyhow::Result<()> {
+    for (relative_path, contents) in mod_files(info, language, scene_bytes)? {
+        let path = folder.join(relative_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(&path, &contents)?;
+    }
+    Ok(())
+}
+
+/// Writes the mod as a zip archive ready to be imported by Junction VIII
+pub fn write_mod_archive(
+    path: &Path,
+    info: &ModInfo,
+    language: Option<&str>,
+    scene_bytes: &[u8],
+) -> anyhow::Result<()> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (relative_path, contents) in mod_files(info, language, scene_bytes)? {
+        let name = relative_path.to_string_lossy().replace('\\', "/");
+        zip.start_file(name, options)?;
+        zip.write_all(&contents)?;
+    }
+    write_atomic(path, &zip.finish()?.into_inner())
+}
+
+fn mod_files(
+    info: &ModInfo,
+    language: Option<&str>,
+    scene_bytes: &[u8],
+) -> anyhow::Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut files = vec![
+        (PathBuf::from(MOD_XML_FILE_NAME), info.to_xml().into_bytes()),
+        (data_scene_path(language), scene_bytes.to_vec()),
+    ];
+    for (path, name) in info.preview_file_names()? {
+        let contents = std::fs::read(path)
+            .map_err(|err| anyhow::anyhow!("Could not read preview {}: {err}", path.display()))?;
+        files.push((PathBuf::from(name), contents));
+    }
+    Ok(files)
+}
+
+/// Generates a random-looking GUID used as mod identifier
+pub fn generate_mod_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let seed = nanos ^ ((std::process::id() as u128) << 64);
+    let mut state = seed as u64 ^ (seed >> 64) as u64 ^ 0x9e3779b97f4a7c15;
+    let mut next = || {
+        // splitmix64
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+    let (high, low) = (next(), next());
+    format!(
+        "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xfff,
+        0x8000 | (low >> 48) & 0x3fff,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, io::Read};
+
+    use super::*;
+
+    fn mod_info() -> ModInfo {
+        ModInfo {
+            id: "00000000-0000-4000-8000-000000000000".to_string(),
+            name: "Hard <Mode>".to_string(),
+            author: "Quistis & Co".to_string(),
+            version: "1.2".to_string(),
+            description: "Harder battles".to_string(),
+            category: "Gameplay".to_string(),
+            preview_files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_to_xml() {
+        let xml = mod_info().to_xml();
+        assert!(xml.contains("<ID>00000000-0000-4000-8000-000000000000</ID>"));
+        assert!(xml.contains("<Name>Hard &lt;Mode&gt;</Name>"));
+        assert!(xml.contains("<Author>Quistis &amp; Co</Author>"));
+        assert!(xml.contains("<Version>1.2</Version>"));
+        assert!(!xml.contains("<PreviewFile>"));
+    }
+
+    #[test]
+    fn test_generate_mod_id() {
+        let id = generate_mod_id();
+        let groups: Vec<usize> = id.split('-').map(|group| group.len()).collect();
+        assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+        assert_eq!(&id[14..15], "4");
+    }
+
+    #[test]
+    fn test_write_mod_folder() {
+        let folder = tempfile::tempdir().unwrap();
+        let preview = folder.path().join("source.png");
+        std::fs::write(&preview, [0x89, 0x50]).unwrap();
+        let mut info = mod_info();
+        info.preview_files.push(preview);
+
+        let output = folder.path().join("mod");
+        write_mod_folder(&output, &info, Some("de"), &[1, 2]).unwrap();
+
+        let xml = std::fs::read_to_string(output.join(MOD_XML_FILE_NAME)).unwrap();
+        assert!(xml.contains("<PreviewFile>source.png</PreviewFile>"));
+        assert_eq!(
+            std::fs::read(output.join("source.png")).unwrap(),
+            vec![0x89, 0x50]
+        );
+        assert_eq!(
+            std::fs::read(output.join(data_scene_path(Some("de")))).unwrap(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_invalid_preview_names() {
+        let folder = tempfile::tempdir().unwrap();
+        let mut info = mod_info();
+        info.preview_files = vec![
+            folder.path().join("a/cover.png"),
+            folder.path().join("b/COVER.png"),
+        ];
+        let err = write_mod_folder(folder.path(), &info, None, &[1]).unwrap_err();
+        assert!(err.to_string().contains("named COVER.png"));
+
+        info.preview_files = vec![folder.path().join("mod.xml")];
+        let err = write_mod_folder(folder.path(), &info, None, &[1]).unwrap_err();
+        assert!(err.to_string().contains("cannot be named mod.xml"));
+        assert_eq!(folder.path().read_dir().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_write_mod_archive() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("mod.zip");
+        write_mod_archive(&path, &mod_info(), None, &[3, 4]).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 2);
+        assert!(archive.by_name(MOD_XML_FILE_NAME).is_ok());
+        let mut contents = Vec::new();
+        archive
+            .by_name("battle/scene.out")
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents, vec![3, 4]);
+    }
+}
@@ -1,2 +1,25 @@
 pub mod battle_structure;
 pub mod battle_names;
+pub mod vanilla;
+pub mod installation;
+pub mod ffnx;
+pub mod junction_viii;
+pub mod history;
+pub mod scene_file;
+pub mod settings;
+pub mod cli;
+pub mod file_kind;
+pub mod encounter_filter;
+pub mod encounter_table;
+pub mod enemy_slots;
+pub mod battlefield;
+pub mod stage_geometry;
+pub mod formation_check;
+pub mod formation_template;
+pub mod formation_tools;
+pub mod formation_diagram;
+pub mod clipboard;
+pub mod encounter_code;
+pub mod encounter_file;
+pub mod relocation;
+pub mod xml;
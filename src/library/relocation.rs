@@ -0,0 +1,262 @@
+use std::fmt::Write;
+
+use super::{
+    battle_structure::BattleStructure, encounter_filter::encounter_summary, history::Command,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationKind {
+    /// The target becomes a copy of the source
+    Copy,
+    /// The target becomes a copy of the source, whose enemy slots are then disabled
+    Move,
+}
+
+impl RelocationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RelocationKind::Copy => "Copy",
+            RelocationKind::Move => "Move",
+        }
+    }
+}
+
+/// Encounter copied or moved to another encounter ID
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relocation {
+    pub kind: RelocationKind,
+    pub from: usize,
+    pub to: usize,
+    /// Summary of the target encounter when it was not free and got overwritten
+    pub overwritten: Option<String>,
+}
+
+/// Relocations made since the file was opened, with the command applying each of them, so that
+/// the undone ones are left out of the report
+#[derive(Debug, Default)]
+pub struct RelocationLog {
+    entries: Vec<LoggedRelocation>,
+}
+
+#[derive(Debug)]
+struct LoggedRelocation {
+    relocation: Relocation,
+    command: Command,
+    undone: bool,
+}
+
+impl RelocationLog {
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Records the relocation applied by the command
+    pub fn record(&mut self, relocation: Relocation, command: Command) {
+        self.entries.push(LoggedRelocation {
+            relocation,
+            command,
+            undone: false,
+        });
+    }
+
+    /// Marks the relocation applied by the command, if any, as undone or redone. A relocation
+    /// undone then discarded from the redo stack is never redone, so it stays out of the report.
+    pub fn set_undone(&mut self, command: &Command, undone: bool) {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.undone != undone && entry.command == *command)
+        {
+            entry.undone = undone;
+        }
+    }
+
+    /// Relocations currently applied, from the oldest to the newest
+    pub fn applied(&self) -> Vec<Relocation> {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.undone)
+            .map(|entry| entry.relocation.clone())
+            .collect()
+    }
+}
+
+/// Whether the encounter has no enabled enemy, i.e. can be reused without losing a battle
+pub fn is_free(battle_structure: &BattleStructure) -> bool {
+    battle_structure.enemies.iter().all(|enemy| !enemy.enabled)
+}
+
+/// First free encounter after `index`, wrapping around
+pub fn next_free(battle_structure_list: &[BattleStructure], index: usize) -> Option<usize> {
+    let count = battle_structure_list.len();
+    (1..count)
+        .map(|offset| (index + offset) % count)
+        .find(|i| is_free(&battle_structure_list[*i]))
+}
+
+/// Changes copying or moving the encounter `from` to `to`, with the relocation to report
+pub fn relocate(
+    battle_structure_list: &[BattleStructure],
+    kind: RelocationKind,
+    from: usize,
+    to: usize,
+) -> anyhow::Result<(Relocation, Vec<(usize, BattleStructure)>)> {
+    let count = battle_structure_list.len();
+    let (Some(source), Some(target)) = (
+        battle_structure_list.get(from),
+        battle_structure_list.get(to),
+    ) else {
+        return Err(anyhow::anyhow!(
+            "Invalid encounter ID: {} (expected 0-{})",
+            from.max(to),
+            count.saturating_sub(1)
+        ));
+    };
+    if from == to {
+        return Err(anyhow::anyhow!(
+            "The encounter cannot be relocated to itself"
+        ));
+    }
+
+    let mut changes = vec![(to, source.clone())];
+    if kind == RelocationKind::Move {
+        let mut freed = source.clone();
+        freed
+            .enemies
+            .iter_mut()
+            .for_each(|enemy| enemy.enabled = false);
+        changes.push((from, freed));
+    }
+    let relocation = Relocation {
+        kind,
+        from,
+        to,
+        overwritten: (!is_free(target)).then(|| encounter_summary(to, target)),
+    };
+    Ok((relocation, changes))
+}
+
+/// Human readable list of the relocations, so that the encounter IDs used by the other game
+/// files (field scripts, world map encounter tables) can be updated
+pub fn relocation_report(relocations: &[Relocation]) -> String {
+    let mut report = String::new();
+    _ = writeln!(report, "Kyactus - FFVIII Battle Structure Editor");
+    _ = writeln!(report, "Relocated encounters: {}", relocations.len());
+    for relocation in relocations {
+        _ = writeln!(
+            report,
+            "  {} {} -> {}",
+            relocation.kind.name(),
+            relocation.from,
+            relocation.to
+        );
+        if let Some(overwritten) = &relocation.overwritten {
+            _ = writeln!(report, "    overwrote {overwritten}");
+        }
+        if relocation.kind == RelocationKind::Move {
+            _ = writeln!(
+                report,
+                "    references to {} must now use {}",
+                relocation.from, relocation.to
+            );
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::{battle_structure::sample_battle_structure, history::History};
+
+    use super::*;
+
+    #[test]
+    fn test_relocate() {
+        let battle_structure = sample_battle_structure();
+        let mut free = battle_structure.clone();
+        free.enemies[0].enabled = false;
+        let list = vec![
+            battle_structure.clone(),
+            free.clone(),
+            battle_structure.clone(),
+        ];
+        assert!(is_free(&list[1]));
+        assert_eq!(next_free(&list, 0), Some(1));
+        assert_eq!(next_free(&list, 1), None);
+
+        let (relocation, changes) = relocate(&list, RelocationKind::Copy, 0, 1).unwrap();
+        assert_eq!(relocation.overwritten, None);
+        assert_eq!(changes, vec![(1, battle_structure.clone())]);
+
+        let (relocation, changes) = relocate(&list, RelocationKind::Move, 2, 0).unwrap();
+        assert_eq!(
+            relocation.overwritten,
+            Some(encounter_summary(0, &battle_structure))
+        );
+        assert_eq!(changes, vec![(0, battle_structure.clone()), (2, free)]);
+
+        assert!(relocate(&list, RelocationKind::Copy, 0, 0).is_err());
+        assert!(relocate(&list, RelocationKind::Copy, 0, 3).is_err());
+    }
+
+    #[test]
+    fn test_relocation_log() {
+        let battle_structure = sample_battle_structure();
+        let mut list = vec![battle_structure.clone(), battle_structure.clone()];
+        list[1].enemies[0].enabled = false;
+        let mut history = History::default();
+        let mut log = RelocationLog::default();
+
+        let (relocation, changes) = relocate(&list, RelocationKind::Move, 0, 1).unwrap();
+        let command = Command::from_changes("Move", &list, changes);
+        log.record(relocation.clone(), command.clone());
+        history.execute(command, &mut list);
+        assert_eq!(log.applied(), vec![relocation.clone()]);
+
+        let undone = history.undo(&mut list).unwrap();
+        log.set_undone(undone, true);
+        assert_eq!(log.applied(), vec![]);
+        let redone = history.redo(&mut list).unwrap();
+        log.set_undone(redone, false);
+        assert_eq!(log.applied(), vec![relocation]);
+
+        // undone then replaced by another edit, it can no longer be redone
+        let undone = history.undo(&mut list).unwrap();
+        log.set_undone(undone, true);
+        let edit = Command::from_changes("Edit", &list, [(0, list[1].clone())]);
+        history.execute(edit, &mut list);
+        assert!(!history.can_redo());
+        assert_eq!(log.applied(), vec![]);
+
+        log.clear();
+        assert_eq!(log.applied(), vec![]);
+    }
+
+    #[test]
+    fn test_relocation_report() {
+        let relocations = [
+            Relocation {
+                kind: RelocationKind::Copy,
+                from: 120,
+                to: 300,
+                overwritten: None,
+            },
+            Relocation {
+                kind: RelocationKind::Move,
+                from: 5,
+                to: 6,
+                overwritten: Some("6 - Dollet: G-Soldier".to_string()),
+            },
+        ];
+        assert_eq!(
+            relocation_report(&relocations),
+            "Kyactus - FFVIII Battle Structure Editor\n\
+             Relocated encounters: 2\n  \
+             Copy 120 -> 300\n  \
+             Move 5 -> 6\n    \
+             overwrote 6 - Dollet: G-Soldier\n    \
+             references to 5 must now use 6\n"
+        );
+    }
+}
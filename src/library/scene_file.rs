@@ -0,0 +1,256 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::battle_structure::{BattleStructure, PackedBattleStructure, BATTLE_STRUCTURE_NUMBER};
+
+/// Number of backups kept next to a file saved in place
+pub const MAX_BACKUPS: usize = 5;
+
+const BACKUP_EXTENSION: &str = "bak";
+
+/// Counter making the temporary files of the writes in flight unique within the process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub fn read_battle_structures(bytes: &[u8]) -> anyhow::Result<Vec<BattleStructure>> {
+    if bytes.len() != size_of::<PackedBattleStructure>() * BATTLE_STRUCTURE_NUMBER {
+        return Err(anyhow::anyhow!("Incorrect bytes size"));
+    }
+
+    let mut battle_structure_list = Vec::with_capacity(BATTLE_STRUCTURE_NUMBER);
+    for i in 0..BATTLE_STRUCTURE_NUMBER {
+        let offset = i * size_of::<PackedBattleStructure>();
+        let packed_bs = PackedBattleStructure::try_from_bytes(
+            bytes
+                .get(offset..offset + size_of::<PackedBattleStructure>())
+                .ok_or(anyhow::anyhow!(
+                    "Could not retrieve data. File size not as expected!"
+                ))?,
+        )?;
+        let battle_structure = packed_bs
+            .try_into_battle_structure()
+            .map_err(|err| anyhow::anyhow!("Encounter {i}: {err}"))?;
+        battle_structure_list.push(battle_structure);
+    }
+    Ok(battle_structure_list)
+}
+
+pub fn write_packed_battle_structure(
+    battle_structure_list: &[BattleStructure],
+) -> anyhow::Result<Vec<u8>> {
+    let mut bytes: Vec<u8> =
+        Vec::with_capacity(BATTLE_STRUCTURE_NUMBER * size_of::<PackedBattleStructure>());
+
+    if battle_structure_list.len() != BATTLE_STRUCTURE_NUMBER {
+        return Err(anyhow::anyhow!(format!(
+            "Battle structure size is incorrect: {}",
+            battle_structure_list.len()
+        )));
+    }
+
+    for battle_structure in battle_structure_list {
+        bytes.extend_from_slice(battle_structure.as_packed_bytes()?.as_ref());
+    }
+    Ok(bytes)
+}
+
+/// Writes the file through a temporary file in the same folder renamed into place,
+/// so that a failure never leaves a truncated file behind. Each write has its own temporary
+/// file (e.g. `.scene.out.1234-0.tmp`), so that concurrent writes to the same file never mix.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or(anyhow::anyhow!("Invalid file path: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp_path, path));
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(anyhow::anyhow!("Could not write {}: {err}", path.display()));
+    }
+    Ok(())
+}
+
+/// Copies the file to a timestamped backup (e.g. `scene.out.1700000000.bak`) and removes the
+/// oldest backups exceeding `max_backups`. Returns the backup path, if the file existed.
+pub fn backup(path: &Path, max_backups: usize) -> anyhow::Result<Option<PathBuf>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let file_name = path
+        .file_name()
+        .ok_or(anyhow::anyhow!("Invalid file path: {}", path.display()))?
+        .to_string_lossy()
+        .to_string();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    // backups made within the same second get an increasing counter to keep their order
+    let backup_path = match backup_stamps(path).pop() {
+        Some((latest_timestamp, counter, _)) if latest_timestamp >= timestamp => {
+            path.with_file_name(format!(
+                "{file_name}.{latest_timestamp}-{}.{BACKUP_EXTENSION}",
+                counter + 1
+            ))
+        }
+        _ => path.with_file_name(format!("{file_name}.{timestamp}.{BACKUP_EXTENSION}")),
+    };
+    std::fs::copy(path, &backup_path).map_err(|err| {
+        anyhow::anyhow!("Could not create backup {}: {err}", backup_path.display())
+    })?;
+
+    let mut backups = backups(path);
+    while backups.len() > max_backups {
+        let oldest = backups.remove(0);
+        std::fs::remove_file(&oldest).map_err(|err| {
+            anyhow::anyhow!("Could not remove old backup {}: {err}", oldest.display())
+        })?;
+    }
+    Ok(Some(backup_path))
+}
+
+/// Backups of the file, from the oldest to the newest
+pub fn backups(path: &Path) -> Vec<PathBuf> {
+    backup_stamps(path)
+        .into_iter()
+        .map(|(_, _, path)| path)
+        .collect()
+}
+
+/// Timestamp, counter and path of the backups of the file, from the oldest to the newest
+fn backup_stamps(path: &Path) -> Vec<(u64, u64, PathBuf)> {
+    let (Some(folder), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", file_name.to_string_lossy());
+    let suffix = format!(".{BACKUP_EXTENSION}");
+    let folder = if folder.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        folder
+    };
+    let Ok(entries) = folder.read_dir() else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<(u64, u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let stamp = name.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+            let (timestamp, counter) = stamp.split_once('-').unwrap_or((stamp, "0"));
+            Some((timestamp.parse().ok()?, counter.parse().ok()?, entry.path()))
+        })
+        .collect();
+    backups.sort();
+    backups
+}
+
+/// Saves the file in place, backing up the previous version first
+pub fn save_in_place(
+    path: &Path,
+    bytes: &[u8],
+    max_backups: usize,
+) -> anyhow::Result<Option<PathBuf>> {
+    let backup_path = backup(path, max_backups)?;
+    write_atomic(path, bytes)?;
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::SAMPLE_BYTES;
+
+    use super::*;
+
+    #[test]
+    fn test_read_incorrect_size() {
+        assert!(read_battle_structures(&[0u8; 128]).is_err());
+        assert!(write_packed_battle_structure(&[]).is_err());
+    }
+
+    #[test]
+    fn test_read_invalid_enemy_id() {
+        let mut bytes = SAMPLE_BYTES.repeat(BATTLE_STRUCTURE_NUMBER);
+        assert_eq!(read_battle_structures(&bytes).unwrap().len(), BATTLE_STRUCTURE_NUMBER);
+
+        // id of the enemy in slot 1 of encounter 3
+        bytes[3 * SAMPLE_BYTES.len() + 57] = 0x00;
+        let err = read_battle_structures(&bytes).unwrap_err();
+        assert!(err.to_string().starts_with("Encounter 3: Invalid enemy id in slot 1"));
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("scene.out");
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+
+        write_atomic(&path, &[4, 5]).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), vec![4, 5]);
+        assert_eq!(folder.path().read_dir().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_write_atomic() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("scene.out");
+        let writes: Vec<_> = (0..8u8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || write_atomic(&path, &[i; 4096]))
+            })
+            .collect();
+        for write in writes {
+            write.join().unwrap().unwrap();
+        }
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.iter().all(|byte| *byte == bytes[0]));
+        assert_eq!(folder.path().read_dir().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_failure() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("missing").join("scene.out");
+        assert!(write_atomic(&path, &[1]).is_err());
+    }
+
+    #[test]
+    fn test_save_in_place_rotates_backups() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("scene.out");
+        assert_eq!(save_in_place(&path, &[0], 2).unwrap(), None);
+
+        for i in 1..=4u8 {
+            let backup_path = save_in_place(&path, &[i], 2).unwrap().unwrap();
+            assert_eq!(std::fs::read(backup_path).unwrap(), vec![i - 1]);
+        }
+
+        let backups = backups(&path);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(std::fs::read(&backups[0]).unwrap(), vec![2]);
+        assert_eq!(std::fs::read(&backups[1]).unwrap(), vec![3]);
+        assert_eq!(std::fs::read(&path).unwrap(), vec![4]);
+    }
+}
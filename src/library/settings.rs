@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::formation_template::FormationTemplate;
+
+/// Maximum number of files listed in File > Open Recent
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// Application state kept between launches
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Recently opened files, from the newest to the oldest
+    pub recent_files: Vec<PathBuf>,
+    pub reopen_last_file: bool,
+    pub last_encounter: usize,
+    pub last_enemy_slot: usize,
+    pub show_history: bool,
+    /// Whether all the enemy slots are edited at once in a grid
+    pub compact_enemies: bool,
+    /// Folder of the `a0stgXXX.x` files extracted from battle.fs, drawn behind the enemies
+    pub stage_folder: Option<PathBuf>,
+    /// Formation templates saved from encounters
+    pub formation_templates: Vec<FormationTemplate>,
+}
+
+impl Settings {
+    /// Moves the file on top of the recent files
+    pub fn add_recent_file(&mut self, path: &Path) {
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    pub fn remove_recent_file(&mut self, path: &Path) {
+        self.recent_files.retain(|recent| recent != path);
+    }
+
+    pub fn last_file(&self) -> Option<&Path> {
+        self.recent_files.first().map(|path| path.as_path())
+    }
+
+    /// Folder of the last opened file, where the open dialogs start
+    pub fn last_folder(&self) -> Option<&Path> {
+        self.last_file()
+            .and_then(|path| path.parent())
+            .filter(|folder| !folder.as_os_str().is_empty())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_add_recent_file() {
+        let mut settings = Settings::default();
+        for i in 0..MAX_RECENT_FILES + 2 {
+            settings.add_recent_file(Path::new(&format!("{i}.out")));
+        }
+        assert_eq!(settings.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(settings.last_file(), Some(Path::new("11.out")));
+
+        settings.add_recent_file(Path::new("5.out"));
+        assert_eq!(settings.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(settings.last_file(), Some(Path::new("5.out")));
+        assert_eq!(settings.recent_files[1], Path::new("11.out"));
+
+        settings.remove_recent_file(Path::new("5.out"));
+        assert_eq!(settings.last_file(), Some(Path::new("11.out")));
+    }
+
+    #[test]
+    fn test_last_folder() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.last_folder(), None);
+        settings.add_recent_file(Path::new("scene.out"));
+        assert_eq!(settings.last_folder(), None);
+        settings.add_recent_file(Path::new("mods/battle/scene.out"));
+        assert_eq!(settings.last_folder(), Some(Path::new("mods/battle")));
+    }
+}
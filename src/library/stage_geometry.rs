@@ -0,0 +1,186 @@
+use std::path::Path;
+
+/// Header of each geometry object of a battle stage file
+const OBJECT_MAGIC: [u8; 4] = [0x01, 0x00, 0x08, 0x00];
+const VERTEX_SIZE: usize = 6;
+const TRIANGLE_SIZE: usize = 20;
+const QUAD_SIZE: usize = 24;
+/// Upper bound of the vertices of an object, used to reject false object headers
+const MAX_OBJECT_VERTICES: usize = 4096;
+
+/// Polygons of a battle stage, with their vertices (x, y, z) in battlefield units
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StageGeometry {
+    pub polygons: Vec<Vec<[i16; 3]>>,
+}
+
+/// File name of the stage geometry in battle.fs, e.g. `a0stg006.x`
+pub fn stage_file_name(stage_id: u8) -> String {
+    format!("a0stg{stage_id:03}.x")
+}
+
+impl StageGeometry {
+    /// Extracts the triangles and quads of the geometry objects of an `a0stgXXX.x` file.
+    ///
+    /// Only the geometry is read: the objects are found by their header and kept when their
+    /// vertex indices and sizes are consistent, while the cameras, textures and animations
+    /// are skipped.
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<StageGeometry> {
+        let mut polygons = Vec::new();
+        let mut offset = 0;
+        while offset + OBJECT_MAGIC.len() <= bytes.len() {
+            if bytes[offset..].starts_with(&OBJECT_MAGIC) {
+                if let Some((object_polygons, end)) = read_object(bytes, offset) {
+                    polygons.extend(object_polygons);
+                    offset = end;
+                    continue;
+                }
+            }
+            offset += 1;
+        }
+
+        if polygons.is_empty() {
+            return Err(anyhow::anyhow!("No stage geometry found"));
+        }
+        Ok(StageGeometry { polygons })
+    }
+
+    /// Reads the geometry of the stage from a folder of files extracted from battle.fs
+    pub fn load(folder: &Path, stage_id: u8) -> anyhow::Result<StageGeometry> {
+        let path = folder.join(stage_file_name(stage_id));
+        let bytes = std::fs::read(&path)
+            .map_err(|err| anyhow::anyhow!("Could not read {}: {err}", path.display()))?;
+        StageGeometry::parse(&bytes)
+            .map_err(|err| anyhow::anyhow!("Invalid stage file {}: {err}", path.display()))
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_i16(bytes: &[u8], offset: usize) -> Option<i16> {
+    read_u16(bytes, offset).map(|value| value as i16)
+}
+
+/// Polygons of the object starting at `offset` and the offset following it, if valid
+fn read_object(bytes: &[u8], offset: usize) -> Option<(Vec<Vec<[i16; 3]>>, usize)> {
+    let vertex_count = read_u16(bytes, offset + 4)? as usize;
+    if !(3..=MAX_OBJECT_VERTICES).contains(&vertex_count) {
+        return None;
+    }
+    let vertices_offset = offset + 6;
+    let vertices = (0..vertex_count)
+        .map(|i| {
+            let vertex_offset = vertices_offset + i * VERTEX_SIZE;
+            Some([
+                read_i16(bytes, vertex_offset)?,
+                read_i16(bytes, vertex_offset + 2)?,
+                read_i16(bytes, vertex_offset + 4)?,
+            ])
+        })
+        .collect::<Option<Vec<[i16; 3]>>>()?;
+
+    // the polygon counts are aligned on 4 bytes, followed by 4 bytes of padding
+    let mut position = vertices_offset + vertex_count * VERTEX_SIZE;
+    position += (4 - position % 4) % 4;
+    let triangle_count = read_u16(bytes, position)? as usize;
+    let quad_count = read_u16(bytes, position + 2)? as usize;
+    position += 8;
+    let end = position + triangle_count * TRIANGLE_SIZE + quad_count * QUAD_SIZE;
+    if triangle_count + quad_count == 0 || end > bytes.len() {
+        return None;
+    }
+
+    let polygon = |indexes: &[usize]| -> Option<Vec<[i16; 3]>> {
+        indexes
+            .iter()
+            .map(|index| vertices.get(*index).copied())
+            .collect()
+    };
+    let mut polygons = Vec::with_capacity(triangle_count + quad_count);
+    for i in 0..triangle_count {
+        let triangle_offset = position + i * TRIANGLE_SIZE;
+        let indexes: Vec<usize> = (0..3)
+            .map(|j| read_u16(bytes, triangle_offset + 2 * j).map(usize::from))
+            .collect::<Option<_>>()?;
+        polygons.push(polygon(&indexes)?);
+    }
+    let quads_offset = position + triangle_count * TRIANGLE_SIZE;
+    for i in 0..quad_count {
+        let quad_offset = quads_offset + i * QUAD_SIZE;
+        let indexes: Vec<usize> = (0..4)
+            .map(|j| read_u16(bytes, quad_offset + 2 * j).map(usize::from))
+            .collect::<Option<_>>()?;
+        // quad vertices are stored in strip order, swap the last two to walk the outline
+        polygons.push(polygon(&[indexes[0], indexes[1], indexes[3], indexes[2]])?);
+    }
+    Some((polygons, end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn object_bytes(vertices: &[[i16; 3]], triangles: &[[u16; 3]], quads: &[[u16; 4]]) -> Vec<u8> {
+        let mut bytes = OBJECT_MAGIC.to_vec();
+        bytes.extend((vertices.len() as u16).to_le_bytes());
+        for vertex in vertices {
+            vertex
+                .iter()
+                .for_each(|value| bytes.extend(value.to_le_bytes()));
+        }
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+        bytes.extend((triangles.len() as u16).to_le_bytes());
+        bytes.extend((quads.len() as u16).to_le_bytes());
+        bytes.extend([0; 4]);
+        for triangle in triangles {
+            triangle
+                .iter()
+                .for_each(|index| bytes.extend(index.to_le_bytes()));
+            bytes.extend([0; TRIANGLE_SIZE - 6]);
+        }
+        for quad in quads {
+            quad.iter()
+                .for_each(|index| bytes.extend(index.to_le_bytes()));
+            bytes.extend([0; QUAD_SIZE - 8]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_stage_geometry() {
+        let vertices = [[0, 0, 0], [100, 0, 0], [0, -50, 100], [100, -50, 100]];
+        let mut bytes = vec![0xAA; 16];
+        bytes.extend(object_bytes(&vertices, &[[0, 1, 2]], &[[0, 1, 2, 3]]));
+        bytes.extend([0x02, 0x00, 0x08, 0x00, 0x20, 0x00, 0x00, 0x00]);
+        bytes.extend(object_bytes(&vertices[1..], &[[2, 1, 0]], &[]));
+
+        let geometry = StageGeometry::parse(&bytes).unwrap();
+        assert_eq!(
+            geometry.polygons,
+            vec![
+                vec![[0, 0, 0], [100, 0, 0], [0, -50, 100]],
+                vec![[0, 0, 0], [100, 0, 0], [100, -50, 100], [0, -50, 100]],
+                vec![[100, -50, 100], [0, -50, 100], [100, 0, 0]],
+            ]
+        );
+        assert_eq!(stage_file_name(6), "a0stg006.x");
+    }
+
+    #[test]
+    fn test_parse_invalid_stage_geometry() {
+        assert!(StageGeometry::parse(&[0; 64]).is_err());
+
+        let vertices = [[0, 0, 0], [100, 0, 0], [0, -50, 100]];
+        let bytes = object_bytes(&vertices, &[[0, 1, 3]], &[]);
+        assert!(StageGeometry::parse(&bytes).is_err());
+
+        let bytes = object_bytes(&vertices, &[[0, 1, 2]], &[]);
+        assert!(StageGeometry::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::io::Cursor;
+
+use binrw::{BinRead, BinWrite};
+
+use super::battle_structure::{BattleStructure, BATTLE_STRUCTURE_NUMBER};
+
+/// Fingerprint files bundled with the editor, one per known vanilla release of scene.out.
+/// The build script includes every `fingerprints/*.kfp` file, see `fingerprints/README.md`.
+const BUNDLED_FINGERPRINTS: &[&[u8]] = include!(concat!(env!("OUT_DIR"), "/fingerprints.rs"));
+
+/// Minimum ratio of matching encounters to consider a baseline as the one the file comes from
+const DETECTION_THRESHOLD: f32 = 0.5;
+
+#[derive(BinRead, BinWrite, Debug)]
+#[brw(little, magic = b"KFP1")]
+struct PackedFingerprints {
+    name_len: u8,
+    #[br(count = name_len)]
+    name: Vec<u8>,
+    fingerprints: [u32; BATTLE_STRUCTURE_NUMBER],
+}
+
+/// Per-encounter fingerprints of a vanilla scene.out
+#[derive(Debug, Clone)]
+pub struct VanillaBaseline {
+    pub name: String,
+    pub fingerprints: Vec<u32>,
+}
+
+impl VanillaBaseline {
+    pub fn from_battle_structures(
+        name: &str,
+        battle_structure_list: &[BattleStructure],
+    ) -> anyhow::Result<VanillaBaseline> {
+        if battle_structure_list.len() != BATTLE_STRUCTURE_NUMBER {
+            return Err(anyhow::anyhow!(
+                "Battle structure size is incorrect: {}",
+                battle_structure_list.len()
+            ));
+        }
+
+        Ok(VanillaBaseline {
+            name: name.to_string(),
+            fingerprints: battle_structure_list
+                .iter()
+                .map(fingerprint)
+                .collect::<anyhow::Result<Vec<u32>>>()?,
+        })
+    }
+
+    pub fn try_from_bytes(bytes: &[u8]) -> anyhow::Result<VanillaBaseline> {
+        let mut cursor = Cursor::new(bytes);
+        let packed = PackedFingerprints::read(&mut cursor)?;
+        Ok(VanillaBaseline {
+            name: String::from_utf8_lossy(&packed.name).to_string(),
+            fingerprints: packed.fingerprints.to_vec(),
+        })
+    }
+
+    pub fn as_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let name = self.name.as_bytes();
+        let name = &name[..name.len().min(u8::MAX as usize)];
+        let packed = PackedFingerprints {
+            name_len: name.len() as u8,
+            name: name.to_vec(),
+            fingerprints: self
+                .fingerprints
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Fingerprint count is incorrect"))?,
+        };
+
+        let mut writer = Cursor::new(Vec::new());
+        packed.write(&mut writer)?;
+        Ok(writer.into_inner())
+    }
+
+    /// Number of encounters whose fingerprint matches the baseline
+    pub fn matching_count(&self, battle_structure_list: &[BattleStructure]) -> usize {
+        self.matching_fingerprint_count(&fingerprints(battle_structure_list))
+    }
+
+    /// Number of fingerprints equal to the baseline one with the same id
+    fn matching_fingerprint_count(&self, fingerprints: &[Option<u32>]) -> usize {
+        fingerprints
+            .iter()
+            .zip(&self.fingerprints)
+            .filter(|(actual, expected)| **actual == Some(**expected))
+            .count()
+    }
+
+    /// Whether the encounter differs from the baseline one with the same id
+    pub fn is_modified(&self, index: usize, battle_structure: &BattleStructure) -> bool {
+        match (self.fingerprints.get(index), fingerprint(battle_structure)) {
+            (Some(expected), Ok(actual)) => *expected != actual,
+            _ => true,
+        }
+    }
+}
+
+/// Baselines shipped within the editor
+pub fn bundled_baselines() -> Vec<VanillaBaseline> {
+    BUNDLED_FINGERPRINTS
+        .iter()
+        .filter_map(|bytes| VanillaBaseline::try_from_bytes(bytes).ok())
+        .collect()
+}
+
+/// Returns the index of the baseline the battle structures most likely come from
+pub fn detect_baseline(
+    baselines: &[VanillaBaseline],
+    battle_structure_list: &[BattleStructure],
+) -> Option<usize> {
+    detect_fingerprints(baselines, &fingerprints(battle_structure_list))
+}
+
+/// Returns the index of the baseline the fingerprints most likely come from
+fn detect_fingerprints(
+    baselines: &[VanillaBaseline],
+    fingerprints: &[Option<u32>],
+) -> Option<usize> {
+    let threshold = (fingerprints.len() as f32 * DETECTION_THRESHOLD) as usize;
+    baselines
+        .iter()
+        .map(|baseline| baseline.matching_fingerprint_count(fingerprints))
+        .enumerate()
+        .filter(|(_, count)| *count > 0 && *count >= threshold)
+        .max_by_key(|(_, count)| *count)
+        .map(|(i, _)| i)
+}
+
+/// FNV-1a hash of the packed bytes of a battle structure
+pub fn fingerprint(battle_structure: &BattleStructure) -> anyhow::Result<u32> {
+    let bytes = battle_structure.as_packed_bytes()?;
+    Ok(bytes.iter().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    }))
+}
+
+/// Fingerprints of the battle structures, `None` for the ones that cannot be packed
+fn fingerprints(battle_structure_list: &[BattleStructure]) -> Vec<Option<u32>> {
+    battle_structure_list
+        .iter()
+        .map(|battle_structure| fingerprint(battle_structure).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::library::battle_structure::sample_battle_structure;
+
+    use super::*;
+
+    fn battle_structure_list() -> Vec<BattleStructure> {
+        (0..BATTLE_STRUCTURE_NUMBER)
+            .map(|_| sample_battle_structure())
+            .collect()
+    }
+
+    #[test]
+    fn test_fingerprint_changes_on_edit() {
+        let mut battle_structure_list = battle_structure_list();
+        let original = fingerprint(&battle_structure_list[0]).unwrap();
+        battle_structure_list[0].enemies[3].level = 10;
+        assert_ne!(fingerprint(&battle_structure_list[0]).unwrap(), original);
+    }
+
+    #[test]
+    fn test_baseline_bytes_roundtrip() {
+        let baseline =
+            VanillaBaseline::from_battle_structures("EN", &battle_structure_list()).unwrap();
+        let bytes = baseline.as_bytes().unwrap();
+        assert_eq!(bytes.len(), 4 + 1 + 2 + 4 * BATTLE_STRUCTURE_NUMBER);
+
+        let parsed = VanillaBaseline::try_from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.name, "EN");
+        assert_eq!(parsed.fingerprints, baseline.fingerprints);
+    }
+
+    #[test]
+    fn test_modified_and_detection() {
+        let mut battle_structure_list = battle_structure_list();
+        let baseline =
+            VanillaBaseline::from_battle_structures("EN", &battle_structure_list).unwrap();
+        battle_structure_list[42].stage_id = 1;
+
+        assert!(baseline.is_modified(42, &battle_structure_list[42]));
+        assert!(!baseline.is_modified(41, &battle_structure_list[41]));
+        assert_eq!(
+            baseline.matching_count(&battle_structure_list),
+            BATTLE_STRUCTURE_NUMBER - 1
+        );
+        assert_eq!(
+            detect_baseline(&[baseline], &battle_structure_list),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_bundled_fingerprints() {
+        let baselines = bundled_baselines();
+        assert_eq!(baselines.len(), BUNDLED_FINGERPRINTS.len());
+
+        for (bytes, baseline) in BUNDLED_FINGERPRINTS.iter().zip(&baselines) {
+            let parsed = VanillaBaseline::try_from_bytes(bytes).unwrap();
+            assert_eq!(parsed.name, baseline.name);
+            assert_eq!(baseline.fingerprints.len(), BATTLE_STRUCTURE_NUMBER);
+
+            let vanilla: Vec<Option<u32>> =
+                baseline.fingerprints.iter().copied().map(Some).collect();
+            let detected = detect_fingerprints(&baselines, &vanilla)
+                .unwrap_or_else(|| panic!("{} is not detected", baseline.name));
+            assert_eq!(baselines[detected].fingerprints, baseline.fingerprints);
+        }
+    }
+
+    #[test]
+    fn test_detection_rejects_unrelated_baseline() {
+        let mut battle_structure_list = battle_structure_list();
+        let baseline =
+            VanillaBaseline::from_battle_structures("EN", &battle_structure_list).unwrap();
+        battle_structure_list
+            .iter_mut()
+            .for_each(|battle_structure| battle_structure.stage_id = 2);
+
+        assert_eq!(detect_baseline(&[baseline], &battle_structure_list), None);
+    }
+}
@@ -0,0 +1,21 @@
+/// Escapes the characters with a special meaning in XML text and attribute values
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml(r#"<Seifer & "Raijin"'s>"#),
+            "&lt;Seifer &amp; &quot;Raijin&quot;&apos;s&gt;"
+        );
+    }
+}
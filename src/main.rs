@@ -1,157 +1,2878 @@
 #![cfg_attr(target_os="windows", windows_subsystem = "windows")]
 
 use async_std::task;
-use egui::{Color32, Context};
+use egui::{Color32, Context, Key, KeyboardShortcut, Modifiers, ViewportCommand};
+use egui_extras::{Column, TableBuilder};
 use kyactus_ff8::library::{
-    battle_names::{ENEMY_NAMES, STAGE_NAMES},
-    battle_structure::{BattleStructure, Enemy, PackedBattleStructure},
+    battle_names::{BATTLE_FLAG_NAMES, ENEMY_NAMES, STAGE_NAMES},
+    battle_structure::{BattleStructure, Enemy, BATTLE_STRUCTURE_NUMBER},
+    battlefield::{MapView, Projection, SlotState, DEFAULT_GRID_SIZE, PARTY_POSITION},
+    cli::{Arguments, USAGE},
+    clipboard::{copy_encounter, copy_enemy, paste, Paste, PasteKind},
+    encounter_code::{decode_encounter, encode_encounter, is_encounter_code},
+    encounter_file::{
+        encounter_file_name, is_encounter_file, read_encounter_file, write_encounter_file,
+        EncounterFileFormat,
+    },
+    encounter_filter::{encounter_summary, EncounterFilter},
+    encounter_table::{copy_cells, filter_rows, paste_cells, sort_rows, TableColumn},
+    enemy_slots::{bulk_set, move_slot, swap_slots, EnemyField, SlotSelection},
+    ffnx::{export_direct, is_language_code, manifest},
+    file_kind::FileKind,
+    formation_check::{
+        check_formation, Bounds, FormationWarning, DEFAULT_BOUNDS, DEFAULT_OVERLAP_RADIUS,
+    },
+    formation_diagram::{diagram_font, diagram_path, export_diagrams, render_diagram, DiagramFormat},
+    formation_template::{builtin_templates, FormationTemplate},
+    formation_tools::FormationTool,
+    history::{Command, Edit, History},
+    installation::{discover_installations, is_scene_file, language_from_path, Installation},
+    junction_viii::{write_mod_archive, write_mod_folder, ModInfo},
+    relocation::{is_free, next_free, relocate, relocation_report, RelocationKind, RelocationLog},
+    scene_file::{
+        read_battle_structures, save_in_place, write_atomic, write_packed_battle_structure,
+        MAX_BACKUPS,
+    },
+    settings::Settings,
+    stage_geometry::{stage_file_name, StageGeometry},
+    vanilla::{bundled_baselines, detect_baseline, VanillaBaseline},
 };
 use rfd::{AsyncFileDialog, AsyncMessageDialog};
 use std::{
+    collections::HashMap,
     future::Future,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
     sync::mpsc::{channel, Receiver, Sender},
 };
 
-const BATTLE_STRUCTURE_NUMBER: usize = 1024;
+const APP_TITLE: &str = "Kyactus - FF8 Battle Structure Editor";
+const HISTORY_CHANGES_SHOWN: usize = 4;
+const MODIFIED_COLOR: Color32 = Color32::from_rgb(230, 160, 40);
+const DIRTY_COLOR: Color32 = Color32::from_rgb(220, 80, 80);
+const MAP_HEIGHT: f32 = 360.0;
+/// Screen radius of the enemy markers on the battlefield map
+const MAP_MARKER_RADIUS: f32 = 9.0;
+const ENABLED_COLOR: Color32 = Color32::from_rgb(80, 180, 80);
+const NOT_LOADED_COLOR: Color32 = Color32::from_rgb(200, 120, 60);
+const PARTY_COLOR: Color32 = Color32::from_rgb(80, 140, 220);
 
 fn main() -> eframe::Result<()> {
+    let arguments = match Arguments::parse(std::env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(err) => {
+            command_line_message(&format!("{err}\n\n{USAGE}"), true);
+            std::process::exit(2);
+        }
+    };
+    if arguments.help {
+        command_line_message(USAGE, false);
+        return Ok(());
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1024.0, 768.0]),
+        persist_window: true,
         ..Default::default()
     };
     eframe::run_native(
-        "Kyactus - FF8 Battle Structure Editor",
+        APP_TITLE,
         native_options,
-        Box::new(|cc| Ok(Box::new(BattleStructureApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(BattleStructureApp::new(cc, arguments)))),
     )
 }
 
+/// Path and contents of a file read from disk
+type OpenedFile = (PathBuf, Vec<u8>);
+/// Path and battle structures written to disk
+type SavedFile = (PathBuf, Vec<BattleStructure>);
+
 pub struct BattleStructureApp {
-    file_bytes_channel: (Sender<Vec<u8>>, Receiver<Vec<u8>>),
+    file_bytes_channel: (Sender<OpenedFile>, Receiver<OpenedFile>),
+    fingerprints_bytes_channel: (Sender<Vec<u8>>, Receiver<Vec<u8>>),
+    preview_path_channel: (Sender<PathBuf>, Receiver<PathBuf>),
+    saved_file_channel: (Sender<SavedFile>, Receiver<SavedFile>),
+    close_confirmed_channel: (Sender<()>, Receiver<()>),
+    stage_folder_channel: (Sender<PathBuf>, Receiver<PathBuf>),
+    encounter_file_channel: (Sender<OpenedFile>, Receiver<OpenedFile>),
+    installations_channel: (Sender<Vec<Installation>>, Receiver<Vec<Installation>>),
+    opened_path: Option<PathBuf>,
+    original_battle_structure_list: Vec<BattleStructure>,
+    saved_battle_structure_list: Vec<BattleStructure>,
     battle_structure_list: Vec<BattleStructure>,
     battle_structure_index: usize,
     enemy_selected_index: usize,
+    vanilla_baselines: Vec<VanillaBaseline>,
+    vanilla_baseline_index: Option<usize>,
+    show_modified_only: bool,
+    show_dirty_only: bool,
+    encounter_filter: EncounterFilter,
+    jump_to_id: usize,
+    /// Whether the encounter browser should scroll to the selected encounter
+    scroll_to_selected: bool,
+    installations: Vec<Installation>,
+    ffnx_export: Option<FfnxExportOptions>,
+    /// Pasted encounter or enemy slot waiting for confirmation
+    pending_paste: Option<PendingPaste>,
+    /// Text of the Import code window, if open
+    code_import: Option<String>,
+    /// Kind and target of the Copy/Move encounter window, if open
+    relocation: Option<(RelocationKind, usize)>,
+    /// Relocations done since the file was opened, for the report
+    relocations: RelocationLog,
+    mod_info: ModInfo,
+    show_mod_package: bool,
+    history: History,
+    merging_edit: bool,
+    settings: Settings,
+    readonly: bool,
+    /// Encounter and enemy slot to select once the file reopened on startup is loaded
+    pending_selection: Option<(usize, usize)>,
+    close_confirmed: bool,
+    title: String,
+    view: View,
+    table: TableState,
+    bulk_set: BulkSetOptions,
+    map: MapState,
+    /// Geometry of the stages read from the stage folder, or the error reading them
+    stage_geometries: HashMap<u8, Result<StageGeometry, String>>,
+    /// Distance under which two enabled enemies are reported as overlapping
+    overlap_radius: f32,
+    /// Selected formation template, indexing the built-in templates then the saved ones
+    formation_template_index: usize,
+    new_template_name: String,
+    formation_tools: FormationToolOptions,
+}
+
+/// Projection, view, grid and dragged enemy of the battlefield map
+struct MapState {
+    projection: Projection,
+    view: MapView,
+    grid_size: i32,
+    snap: bool,
+    /// Enemy slot being dragged, if any
+    dragging: Option<usize>,
+}
+
+/// Paste or import previewed before replacing the encounter
+struct PendingPaste {
+    /// Encounter to replace
+    index: usize,
+    /// What is pasted, e.g. `paste enemy slot 2`
+    description: String,
+    battle_structure: BattleStructure,
+    /// Whether the user can choose another encounter to replace
+    retarget: bool,
+    /// Name and contents of the imported file, read again when another encounter is chosen as
+    /// a text file takes its missing fields from the encounter it replaces
+    file: Option<(String, Vec<u8>)>,
+}
+
+/// Parameters and enemy slots of the formation tools
+struct FormationToolOptions {
+    selection: SlotSelection,
+    spacing: i16,
+    factor: f32,
+    offset: [i16; 3],
+}
+
+/// Field, value and enemy slots of the next bulk set
+struct BulkSetOptions {
+    field: EnemyField,
+    value: i32,
+    selection: SlotSelection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    Editor,
+    Table,
+}
+
+/// Columns, filters, sorting and selection of the encounter table
+struct TableState {
+    columns: Vec<TableColumn>,
+    /// Filter of each column, aligned with `columns`
+    filters: Vec<String>,
+    /// Sorted column and whether the order is descending
+    sort: Option<(TableColumn, bool)>,
+    /// First and last selected cells, as positions in the displayed rows and columns
+    selection: Option<((usize, usize), (usize, usize))>,
+    /// Cell being edited and its text
+    editing: Option<(usize, TableColumn, String)>,
+}
+
+impl TableState {
+    fn new() -> Self {
+        let columns = TableColumn::all();
+        Self {
+            filters: vec![String::new(); columns.len()],
+            columns,
+            sort: None,
+            selection: None,
+            editing: None,
+        }
+    }
+
+    /// Encounter indexes of the displayed rows
+    fn rows(&self, battle_structure_list: &[BattleStructure]) -> Vec<usize> {
+        let mut rows = filter_rows(battle_structure_list, &self.columns, &self.filters);
+        if let Some((column, descending)) = self.sort {
+            sort_rows(battle_structure_list, &mut rows, column, descending);
+        }
+        rows
+    }
+
+    /// Row and column ranges of the selected cells
+    fn selected_ranges(&self) -> Option<(RangeInclusive<usize>, RangeInclusive<usize>)> {
+        let ((first_row, first_column), (last_row, last_column)) = self.selection?;
+        Some((
+            first_row.min(last_row)..=first_row.max(last_row),
+            first_column.min(last_column)..=first_column.max(last_column),
+        ))
+    }
+}
+
+/// Serialized battle structures waiting to be written to disk
+struct SaveRequest {
+    contents: Vec<u8>,
+    battle_structure_list: Vec<BattleStructure>,
+    sender: Sender<SavedFile>,
+    ctx: Context,
+}
+
+impl SaveRequest {
+    async fn write(self, path: PathBuf, in_place: bool) {
+        let result = if in_place {
+            save_in_place(&path, &self.contents, MAX_BACKUPS).map(|_| ())
+        } else {
+            write_atomic(&path, &self.contents)
+        };
+        match result {
+            Ok(()) => {
+                let _ = self.sender.send((path, self.battle_structure_list));
+                self.ctx.request_repaint();
+            }
+            Err(err) => {
+                error_dialog(&err.to_string()).await;
+            }
+        }
+    }
+}
+
+struct FfnxExportOptions {
+    language: String,
+    write_manifest: bool,
 }
 
 impl BattleStructureApp {
     /// Called once before the first frame.
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, arguments: Arguments) -> Self {
+        let settings: Settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
+        let mut app = Self {
             file_bytes_channel: channel(),
+            fingerprints_bytes_channel: channel(),
+            preview_path_channel: channel(),
+            saved_file_channel: channel(),
+            close_confirmed_channel: channel(),
+            stage_folder_channel: channel(),
+            encounter_file_channel: channel(),
+            installations_channel: channel(),
+            opened_path: None,
+            original_battle_structure_list: Vec::new(),
+            saved_battle_structure_list: Vec::new(),
             battle_structure_list: Vec::new(),
             battle_structure_index: 0,
             enemy_selected_index: 0,
+            vanilla_baselines: bundled_baselines(),
+            vanilla_baseline_index: None,
+            show_modified_only: false,
+            show_dirty_only: false,
+            encounter_filter: EncounterFilter::default(),
+            jump_to_id: 0,
+            scroll_to_selected: true,
+            installations: Vec::new(),
+            ffnx_export: None,
+            pending_paste: None,
+            code_import: None,
+            relocation: None,
+            relocations: RelocationLog::default(),
+            mod_info: ModInfo::default(),
+            show_mod_package: false,
+            history: History::default(),
+            merging_edit: false,
+            settings,
+            readonly: arguments.readonly,
+            pending_selection: None,
+            close_confirmed: false,
+            title: APP_TITLE.to_string(),
+            view: View::Editor,
+            table: TableState::new(),
+            bulk_set: BulkSetOptions {
+                field: EnemyField::Level,
+                value: 1,
+                selection: SlotSelection::Enabled,
+            },
+            map: MapState {
+                projection: Projection::TopDown,
+                view: MapView::default(),
+                grid_size: DEFAULT_GRID_SIZE,
+                snap: true,
+                dragging: None,
+            },
+            stage_geometries: HashMap::new(),
+            overlap_radius: DEFAULT_OVERLAP_RADIUS,
+            formation_template_index: 0,
+            new_template_name: String::new(),
+            formation_tools: FormationToolOptions {
+                selection: SlotSelection::Enabled,
+                spacing: 1500,
+                factor: 1.2,
+                offset: [0, 0, 0],
+            },
+        };
+
+        app.scan_installations(&cc.egui_ctx);
+        if let Some(path) = arguments.path {
+            app.pending_selection = Some((
+                arguments.encounter.unwrap_or_default(),
+                arguments.slot.unwrap_or_default(),
+            ));
+            app.open_path(path, &cc.egui_ctx);
+        } else if app.settings.reopen_last_file {
+            if let Some(path) = app.settings.last_file().map(Path::to_path_buf) {
+                app.pending_selection =
+                    Some((app.settings.last_encounter, app.settings.last_enemy_slot));
+                app.open_path(path, &cc.egui_ctx);
+            }
+        }
+        app
+    }
+
+    /// Whether the battle structure differs from the last opened or saved one
+    fn is_encounter_dirty(&self, index: usize) -> bool {
+        self.battle_structure_list.get(index) != self.saved_battle_structure_list.get(index)
+    }
+
+    /// Indexes of the encounters listed by the browser with its current filters
+    fn listed_encounters(&self) -> Vec<usize> {
+        let modified_only = self.show_modified_only && self.vanilla_baseline().is_some();
+        self.encounter_filter
+            .filter(&self.battle_structure_list)
+            .into_iter()
+            .filter(|i| !modified_only || self.is_modified(*i))
+            .filter(|i| !self.show_dirty_only || self.is_encounter_dirty(*i))
+            .collect()
+    }
+
+    /// Saves the report of the relocations currently applied where the user chooses
+    fn save_relocation_report(&self) {
+        let report = relocation_report(&self.relocations.applied());
+        let task = AsyncFileDialog::new()
+            .add_filter("Text", &["txt"])
+            .set_file_name("relocations.txt")
+            .save_file();
+        execute(async move {
+            let file = task.await;
+            if let Some(file) = file {
+                if let Err(err) = write_atomic(file.path(), report.as_bytes()) {
+                    error_dialog(&err.to_string()).await;
+                }
+            }
+        });
+    }
+
+    /// Renders the diagram of the selected encounter and saves it where the user chooses
+    fn export_diagram(&self, format: DiagramFormat) {
+        let index = self.battle_structure_index;
+        let Some(battle_structure) = self.battle_structure_list.get(index) else {
+            return;
+        };
+        let extension = format.extension();
+        let task = AsyncFileDialog::new()
+            .add_filter("Image", &[extension])
+            .set_file_name(format!("encounter_{index:04}.{extension}"))
+            .save_file();
+        let diagram =
+            diagram_font().and_then(|font| render_diagram(index, battle_structure, format, &font));
+        match diagram {
+            Ok(contents) => {
+                execute(async move {
+                    let file = task.await;
+                    if let Some(file) = file {
+                        if let Err(err) = write_atomic(file.path(), &contents) {
+                            error_dialog(&err.to_string()).await;
+                        }
+                    }
+                });
+            }
+            Err(err) => {
+                execute(async move {
+                    error_dialog(&err.to_string()).await;
+                });
+            }
+        }
+    }
+
+    /// Renders the diagrams of the listed encounters into the folder the user chooses
+    fn export_listed_diagrams(&self, format: DiagramFormat) {
+        let battle_structure_list = self.battle_structure_list.clone();
+        let indexes = self.listed_encounters();
+        let task = AsyncFileDialog::new()
+            .set_title("Select the folder of the diagrams")
+            .pick_folder();
+        execute(async move {
+            let Some(folder) = task.await else {
+                return;
+            };
+            let existing = indexes
+                .iter()
+                .filter(|index| diagram_path(folder.path(), **index, format).exists())
+                .count();
+            if existing > 0 && !confirm_overwrite_dialog(existing).await {
+                return;
+            }
+            if let Err(err) =
+                export_diagrams(&battle_structure_list, &indexes, folder.path(), format)
+            {
+                error_dialog(&err.to_string()).await;
+            }
+        });
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.battle_structure_list != self.saved_battle_structure_list
+    }
+
+    fn window_title(&self) -> String {
+        match &self.opened_path {
+            Some(path) => {
+                let file_name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let dirty_marker = if self.is_dirty() { " *" } else { "" };
+                let readonly_marker = if self.readonly { " [read-only]" } else { "" };
+                format!("{APP_TITLE} - {file_name}{dirty_marker}{readonly_marker}")
+            }
+            None => APP_TITLE.to_string(),
+        }
+    }
+
+    fn open_file_dialog(&self, ctx: &Context) {
+        let sender = self.file_bytes_channel.0.clone();
+        let is_dirty = self.is_dirty();
+        let folder = self.dialog_folder();
+        let ctx = ctx.clone();
+        execute(async move {
+            if is_dirty && !confirm_discard_dialog().await {
+                return;
+            }
+            let file = AsyncFileDialog::new()
+                .set_title("Select scene.out file")
+                .add_filter("scene.out", &["out"])
+                .set_directory(folder)
+                .pick_file()
+                .await;
+            if let Some(file) = file {
+                let bytes = file.read().await;
+                let _ = sender.send((file.path().to_path_buf(), bytes));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Scans the disk for FFVIII installations in the background
+    fn scan_installations(&self, ctx: &Context) {
+        let sender = self.installations_channel.0.clone();
+        let ctx = ctx.clone();
+        execute(async move {
+            let _ = sender.send(find_installations());
+            ctx.request_repaint();
+        });
+    }
+
+    fn undo(&mut self) {
+        if let Some(command) = self.history.undo(&mut self.battle_structure_list) {
+            self.relocations.set_undone(command, true);
+            if let Some(index) = focused_index(command) {
+                self.battle_structure_index = index;
+            }
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(command) = self.history.redo(&mut self.battle_structure_list) {
+            self.relocations.set_undone(command, false);
+            if let Some(index) = focused_index(command) {
+                self.battle_structure_index = index;
+            }
+        }
+    }
+
+    /// Folder where the open dialogs start: the one of the opened file, else of the last one
+    fn dialog_folder(&self) -> PathBuf {
+        self.opened_path
+            .as_deref()
+            .and_then(|path| path.parent())
+            .filter(|folder| !folder.as_os_str().is_empty())
+            .or(self.settings.last_folder())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    }
+
+    fn opened_language(&self) -> Option<String> {
+        self.opened_path.as_deref().and_then(language_from_path)
+    }
+
+    fn can_save(&self) -> bool {
+        self.opened_path.is_some() && self.can_save_as()
+    }
+
+    /// Whether the battle structures can be written to a new file, i.e. not in read-only mode
+    fn can_save_as(&self) -> bool {
+        !self.readonly && !self.battle_structure_list.is_empty()
+    }
+
+    /// Writes the battle structures back to the opened file, keeping a backup of it
+    fn save_file(&self, ctx: &Context) {
+        let (Some(path), Some(request)) = (self.opened_path.clone(), self.save_request(ctx)) else {
+            return;
+        };
+        execute(request.write(path, true));
+    }
+
+    fn save_as(&self, ctx: &Context) {
+        let Some(request) = self.save_request(ctx) else {
+            return;
+        };
+        let task = AsyncFileDialog::new().set_file_name("scene.out").save_file();
+        execute(async move {
+            if let Some(file) = task.await {
+                request.write(file.path().to_path_buf(), false).await;
+            }
+        });
+    }
+
+    fn save_request(&self, ctx: &Context) -> Option<SaveRequest> {
+        if !self.can_save_as() {
+            return None;
+        }
+        match write_packed_battle_structure(&self.battle_structure_list) {
+            Ok(contents) => Some(SaveRequest {
+                contents,
+                battle_structure_list: self.battle_structure_list.clone(),
+                sender: self.saved_file_channel.0.clone(),
+                ctx: ctx.clone(),
+            }),
+            Err(err) => {
+                execute(async move {
+                    error_dialog(&err.to_string()).await;
+                });
+                None
+            }
+        }
+    }
+
+    /// Opens a dropped file according to its kind
+    fn open_dropped_path(&self, path: PathBuf, ctx: &Context) {
+        match FileKind::from_path(&path) {
+            FileKind::Scene | FileKind::Unknown => self.open_path(path, ctx),
+            FileKind::Fingerprints => self.load_fingerprints_path(path, ctx),
+            FileKind::Encounter => self.import_encounter_path(path, ctx),
+            kind @ (FileKind::Archive | FileKind::Patch) => {
+                execute(async move {
+                    error_dialog(&format!(
+                        "Opening {} files is not supported yet: {}",
+                        kind.name(),
+                        path.display()
+                    ))
+                    .await;
+                });
+            }
+        }
+    }
+
+    /// Reads a single encounter file, previewed before replacing an encounter. Other files with
+    /// the same extensions, e.g. a scene.out dump named `.bin`, are opened as a scene.out.
+    fn import_encounter_path(&self, path: PathBuf, ctx: &Context) {
+        let encounter_sender = self.encounter_file_channel.0.clone();
+        let scene_sender = self.file_bytes_channel.0.clone();
+        let is_opened = !self.battle_structure_list.is_empty();
+        let is_dirty = self.is_dirty();
+        let ctx = ctx.clone();
+        execute(async move {
+            let bytes = match async_std::fs::read(&path).await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    error_dialog(&format!("Could not read {}: {err}", path.display())).await;
+                    return;
+                }
+            };
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if !is_encounter_file(&file_name, &bytes) {
+                if is_dirty && !confirm_discard_dialog().await {
+                    return;
+                }
+                let _ = scene_sender.send((path, bytes));
+            } else if is_opened {
+                let _ = encounter_sender.send((path, bytes));
+            } else {
+                error_dialog("Open a scene.out file before importing an encounter").await;
+                return;
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Saves the selected encounter as a standalone file where the user chooses
+    fn export_encounter(&self, format: EncounterFileFormat) {
+        let index = self.battle_structure_index;
+        let Some(battle_structure) = self.battle_structure_list.get(index) else {
+            return;
+        };
+        let task = AsyncFileDialog::new()
+            .add_filter("Encounter", &[format.extension()])
+            .set_file_name(encounter_file_name(index, format))
+            .save_file();
+        match write_encounter_file(index, battle_structure, format) {
+            Ok(contents) => {
+                execute(async move {
+                    let file = task.await;
+                    if let Some(file) = file {
+                        if let Err(err) = write_atomic(file.path(), &contents) {
+                            error_dialog(&err.to_string()).await;
+                        }
+                    }
+                });
+            }
+            Err(err) => {
+                execute(async move {
+                    error_dialog(&err.to_string()).await;
+                });
+            }
+        }
+    }
+
+    fn load_fingerprints_path(&self, path: PathBuf, ctx: &Context) {
+        let sender = self.fingerprints_bytes_channel.0.clone();
+        let ctx = ctx.clone();
+        execute(async move {
+            match async_std::fs::read(&path).await {
+                Ok(bytes) => {
+                    let _ = sender.send(bytes);
+                    ctx.request_repaint();
+                }
+                Err(err) => {
+                    error_dialog(&format!("Could not read {}: {err}", path.display())).await;
+                }
+            }
+        });
+    }
+
+    fn open_path(&self, path: PathBuf, ctx: &Context) {
+        let sender = self.file_bytes_channel.0.clone();
+        let is_dirty = self.is_dirty();
+        let ctx = ctx.clone();
+        execute(async move {
+            if is_dirty && !confirm_discard_dialog().await {
+                return;
+            }
+            match async_std::fs::read(&path).await {
+                Ok(bytes) => {
+                    let _ = sender.send((path, bytes));
+                    ctx.request_repaint();
+                }
+                Err(err) => {
+                    error_dialog(&format!("Could not read {}: {err}", path.display())).await;
+                }
+            }
+        });
+    }
+
+    /// Reads the geometry of the stage from the stage folder, unless already read
+    fn load_stage_geometry(&mut self, stage_id: u8) {
+        if let Some(folder) = self.settings.stage_folder.as_deref() {
+            self.stage_geometries.entry(stage_id).or_insert_with(|| {
+                StageGeometry::load(folder, stage_id).map_err(|err| err.to_string())
+            });
+        }
+    }
+
+    fn vanilla_baseline(&self) -> Option<&VanillaBaseline> {
+        self.vanilla_baseline_index
+            .and_then(|i| self.vanilla_baselines.get(i))
+    }
+
+    fn is_modified(&self, index: usize) -> bool {
+        match (self.vanilla_baseline(), self.battle_structure_list.get(index)) {
+            (Some(baseline), Some(battle_structure)) => {
+                baseline.is_modified(index, battle_structure)
+            }
+            _ => false,
         }
     }
 }
 
-impl eframe::App for BattleStructureApp {
-    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
-        if let Ok(bytes) = self.file_bytes_channel.1.try_recv() {
-            match read_battle_structures(&bytes) {
-                Ok(battle_structure_list) => {
-                    self.battle_structure_list = battle_structure_list;
-                    self.battle_structure_index = 0;
-                    self.enemy_selected_index = 0;
-                }
+impl eframe::App for BattleStructureApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if !self.battle_structure_list.is_empty() {
+            self.settings.last_encounter = self.battle_structure_index;
+            self.settings.last_enemy_slot = self.enemy_selected_index;
+        }
+        eframe::set_value(storage, eframe::APP_KEY, &self.settings);
+    }
+
+    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        if let Ok((path, bytes)) = self.file_bytes_channel.1.try_recv() {
+            match read_battle_structures(&bytes) {
+                Ok(battle_structure_list) => {
+                    self.vanilla_baseline_index =
+                        detect_baseline(&self.vanilla_baselines, &battle_structure_list);
+                    self.opened_path = Some(path);
+                    self.original_battle_structure_list = battle_structure_list.clone();
+                    self.saved_battle_structure_list = battle_structure_list.clone();
+                    self.battle_structure_list = battle_structure_list;
+                    (self.battle_structure_index, self.enemy_selected_index) =
+                        self.pending_selection.take().unwrap_or_default();
+                    self.history.clear();
+                    self.relocations.clear();
+                    if let Some(path) = &self.opened_path {
+                        self.settings.add_recent_file(path);
+                    }
+                }
+                Err(err) => {
+                    execute(async move {
+                        error_dialog(&err.to_string()).await;
+                    });
+                }
+            }
+        }
+
+        if let Ok(installations) = self.installations_channel.1.try_recv() {
+            self.installations = installations;
+        }
+
+        if let Ok(bytes) = self.fingerprints_bytes_channel.1.try_recv() {
+            match VanillaBaseline::try_from_bytes(&bytes) {
+                Ok(baseline) => {
+                    self.vanilla_baselines.push(baseline);
+                    if !self.battle_structure_list.is_empty() {
+                        self.vanilla_baseline_index =
+                            detect_baseline(&self.vanilla_baselines, &self.battle_structure_list);
+                    }
+                }
+                Err(err) => {
+                    execute(async move {
+                        error_dialog(&err.to_string()).await;
+                    });
+                }
+            }
+        }
+
+        egui::TopBottomPanel::top("app_top_bar")
+            .frame(egui::Frame::none().inner_margin(4.0))
+            .show(ctx, |ui| {
+                egui::menu::bar(ui, |ui| {
+                    ui.menu_button("File", |ui| {
+                        ui.set_max_width(200.0);
+
+                        if ui.button("Open...").clicked() {
+                            self.open_file_dialog(ui.ctx());
+                            ui.close_menu();
+                        }
+
+                        ui.menu_button("Open Recent", |ui| {
+                            recent_files_contents(ui, self);
+                        });
+
+                        ui.menu_button("Installations", |ui| {
+                            installations_contents(ui, self);
+                        });
+
+                        if ui
+                            .add_enabled(
+                                self.can_save(),
+                                egui::Button::new("Save").shortcut_text("Ctrl+S"),
+                            )
+                            .clicked()
+                        {
+                            self.save_file(ui.ctx());
+                            ui.close_menu();
+                        }
+
+                        let save_as_enabled = self.can_save_as();
+                        if ui
+                            .add_enabled(save_as_enabled, egui::Button::new("Save as..."))
+                            .clicked()
+                        {
+                            self.save_as(ui.ctx());
+                            ui.close_menu();
+                        }
+
+                        if ui
+                            .add_enabled(save_as_enabled, egui::Button::new("Export for FFNx..."))
+                            .clicked()
+                        {
+                            self.ffnx_export = Some(FfnxExportOptions {
+                                language: self.opened_language().unwrap_or("en".to_string()),
+                                write_manifest: true,
+                            });
+                            ui.close_menu();
+                        }
+
+                        if ui
+                            .add_enabled(
+                                save_as_enabled,
+                                egui::Button::new("Package as Junction VIII mod..."),
+                            )
+                            .clicked()
+                        {
+                            self.show_mod_package = true;
+                            ui.close_menu();
+                        }
+
+                        let encounter_enabled = !self.battle_structure_list.is_empty();
+                        if ui
+                            .add_enabled(
+                                encounter_enabled,
+                                egui::Button::new("Import encounter..."),
+                            )
+                            .on_hover_text("Replace an encounter with a .bin or .txt file")
+                            .clicked()
+                        {
+                            let sender = self.encounter_file_channel.0.clone();
+                            let task = AsyncFileDialog::new()
+                                .add_filter("Encounter", &["bin", "txt"])
+                                .pick_file();
+                            let ctx = ui.ctx().clone();
+                            execute(async move {
+                                if let Some(file) = task.await {
+                                    let bytes = file.read().await;
+                                    let _ = sender.send((file.path().to_path_buf(), bytes));
+                                    ctx.request_repaint();
+                                }
+                            });
+                            ui.close_menu();
+                        }
+                        ui.add_enabled_ui(encounter_enabled, |ui| {
+                            ui.menu_button("Export encounter", |ui| {
+                                if ui.button("As binary (.bin)...").clicked() {
+                                    self.export_encounter(EncounterFileFormat::Binary);
+                                    ui.close_menu();
+                                }
+                                if ui.button("As text (.txt)...").clicked() {
+                                    self.export_encounter(EncounterFileFormat::Text);
+                                    ui.close_menu();
+                                }
+                            });
+                        });
+
+                        let diagram_enabled = !self.battle_structure_list.is_empty();
+                        ui.add_enabled_ui(diagram_enabled, |ui| {
+                            ui.menu_button("Export formation diagram", |ui| {
+                                for format in [DiagramFormat::Png, DiagramFormat::Svg] {
+                                    let extension = format.extension().to_uppercase();
+                                    if ui.button(format!("Encounter as {extension}...")).clicked() {
+                                        self.export_diagram(format);
+                                        ui.close_menu();
+                                    }
+                                }
+                                ui.separator();
+                                for format in [DiagramFormat::Png, DiagramFormat::Svg] {
+                                    let extension = format.extension().to_uppercase();
+                                    if ui
+                                        .button(format!("Listed encounters as {extension}..."))
+                                        .on_hover_text("Encounters shown by the browser filters")
+                                        .clicked()
+                                    {
+                                        self.export_listed_diagrams(format);
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        });
+                        let report_enabled = !self.relocations.applied().is_empty();
+                        if ui
+                            .add_enabled(
+                                report_enabled,
+                                egui::Button::new("Save relocation report..."),
+                            )
+                            .on_hover_text("Encounters copied or moved to another ID")
+                            .clicked()
+                        {
+                            self.save_relocation_report();
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+
+                        if ui.button("Load vanilla fingerprints...").clicked() {
+                            let sender = self.fingerprints_bytes_channel.0.clone();
+                            let task = AsyncFileDialog::new()
+                                .set_title("Select vanilla fingerprints file")
+                                .add_filter("Fingerprints", &["kfp"])
+                                .set_directory(self.dialog_folder())
+                                .pick_file();
+                            let ctx = ui.ctx().clone();
+                            execute(async move {
+                                let file = task.await;
+                                if let Some(file) = file {
+                                    let bytes = file.read().await;
+                                    let _ = sender.send(bytes);
+                                    ctx.request_repaint();
+                                }
+                            });
+                            ui.close_menu();
+                        }
+
+                        let export_enabled = !self.battle_structure_list.is_empty();
+                        if ui
+                            .add_enabled(
+                                export_enabled,
+                                egui::Button::new("Export vanilla fingerprints..."),
+                            )
+                            .on_hover_text("Use it only on an untouched scene.out")
+                            .clicked()
+                        {
+                            let task = AsyncFileDialog::new()
+                                .add_filter("Fingerprints", &["kfp"])
+                                .set_file_name("vanilla.kfp")
+                                .save_file();
+                            let battle_structure_list = self.battle_structure_list.clone();
+                            execute(async move {
+                                let Some(file) = task.await else {
+                                    return;
+                                };
+                                // The file name, e.g. `steam-2013-en`, names the release
+                                let name = file.path().file_stem().unwrap_or_default();
+                                let result = VanillaBaseline::from_battle_structures(
+                                    &name.to_string_lossy(),
+                                    &battle_structure_list,
+                                )
+                                .and_then(|baseline| baseline.as_bytes())
+                                .and_then(|contents| write_atomic(file.path(), &contents));
+                                if let Err(err) = result {
+                                    error_dialog(&err.to_string()).await;
+                                }
+                            });
+                            ui.close_menu();
+                        }
+                    });
+
+                    ui.menu_button("Edit", |ui| {
+                        ui.set_max_width(200.0);
+
+                        if ui
+                            .add_enabled(
+                                self.history.can_undo(),
+                                egui::Button::new("Undo").shortcut_text("Ctrl+Z"),
+                            )
+                            .clicked()
+                        {
+                            self.undo();
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(
+                                self.history.can_redo(),
+                                egui::Button::new("Redo").shortcut_text("Ctrl+Shift+Z"),
+                            )
+                            .clicked()
+                        {
+                            self.redo();
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+                        let selected = self.battle_structure_list.get(self.battle_structure_index);
+                        if ui
+                            .add_enabled(
+                                selected.is_some(),
+                                egui::Button::new("Copy encounter").shortcut_text("Ctrl+C"),
+                            )
+                            .on_hover_text("Paste it with Ctrl+V into another encounter")
+                            .clicked()
+                        {
+                            if let Some(battle_structure) = selected {
+                                ui.ctx().copy_text(copy_encounter(battle_structure));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(selected.is_some(), egui::Button::new("Copy as code"))
+                            .on_hover_text("Short code of the encounter to share in a message")
+                            .clicked()
+                        {
+                            match selected.map(encode_encounter) {
+                                Some(Ok(code)) => ui.ctx().copy_text(code),
+                                Some(Err(err)) => {
+                                    execute(async move {
+                                        error_dialog(&err.to_string()).await;
+                                    });
+                                }
+                                None => {}
+                            }
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(selected.is_some(), egui::Button::new("Import code..."))
+                            .clicked()
+                        {
+                            self.code_import = Some(String::new());
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+                        for kind in [RelocationKind::Copy, RelocationKind::Move] {
+                            let label = format!("{} encounter to ID...", kind.name());
+                            if ui
+                                .add_enabled(selected.is_some(), egui::Button::new(label))
+                                .clicked()
+                            {
+                                let index = self.battle_structure_index;
+                                let target = next_free(&self.battle_structure_list, index)
+                                    .unwrap_or(index);
+                                self.relocation = Some((kind, target));
+                                ui.close_menu();
+                            }
+                        }
+                        let enemy = selected
+                            .and_then(|selected| selected.enemies.get(self.enemy_selected_index));
+                        if ui
+                            .add_enabled(
+                                enemy.is_some(),
+                                egui::Button::new(format!(
+                                    "Copy enemy slot {}",
+                                    self.enemy_selected_index
+                                )),
+                            )
+                            .on_hover_text("Paste it with Ctrl+V into the selected enemy slot")
+                            .clicked()
+                        {
+                            if let Some(enemy) = enemy {
+                                ui.ctx().copy_text(copy_enemy(enemy));
+                            }
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+                        ui.checkbox(&mut self.settings.show_history, "Show history");
+                    });
+
+                    ui.menu_button("View", |ui| {
+                        ui.set_max_width(200.0);
+
+                        if ui
+                            .radio_value(&mut self.view, View::Editor, "Encounter editor")
+                            .clicked()
+                            || ui
+                                .radio_value(&mut self.view, View::Table, "Encounter table")
+                                .clicked()
+                        {
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+                        if ui
+                            .button("Stage geometry folder...")
+                            .on_hover_text(
+                                "Folder of the a0stgXXX.x files extracted from battle.fs",
+                            )
+                            .clicked()
+                        {
+                            let sender = self.stage_folder_channel.0.clone();
+                            let task = AsyncFileDialog::new()
+                                .set_title("Select the folder of the a0stgXXX.x files")
+                                .pick_folder();
+                            let ctx = ui.ctx().clone();
+                            execute(async move {
+                                if let Some(folder) = task.await {
+                                    let _ = sender.send(folder.path().to_path_buf());
+                                    ctx.request_repaint();
+                                }
+                            });
+                            ui.close_menu();
+                        }
+                    });
+                });
+            });
+
+        if self.settings.show_history {
+            egui::SidePanel::right("history_panel")
+                .resizable(true)
+                .show(ctx, |ui| history_contents(ui, self));
+        }
+
+        if let Ok((path, battle_structure_list)) = self.saved_file_channel.1.try_recv() {
+            self.settings.add_recent_file(&path);
+            self.opened_path = Some(path);
+            self.saved_battle_structure_list = battle_structure_list;
+        }
+
+        if self.close_confirmed_channel.1.try_recv().is_ok() {
+            self.close_confirmed = true;
+            ctx.send_viewport_cmd(ViewportCommand::Close);
+        }
+
+        if ctx.input(|i| i.viewport().close_requested()) && !self.close_confirmed && self.is_dirty()
+        {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            let sender = self.close_confirmed_channel.0.clone();
+            let ctx = ctx.clone();
+            execute(async move {
+                if confirm_discard_dialog().await {
+                    let _ = sender.send(());
+                    ctx.request_repaint();
+                }
+            });
+        }
+
+        let dropped_paths: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        // only one scene.out can be opened at a time, the last dropped one wins
+        if let Some(path) = dropped_paths.into_iter().last() {
+            self.open_dropped_path(path, ctx);
+        }
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            drop_overlay(ctx);
+        }
+
+        let title = self.window_title();
+        if title != self.title {
+            ctx.send_viewport_cmd(ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
+
+        if !ctx.wants_keyboard_input() && !self.battle_structure_list.is_empty() {
+            let save_shortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+            if ctx.input_mut(|i| i.consume_shortcut(&save_shortcut)) && self.can_save() {
+                self.save_file(ctx);
+            }
+
+            let redo_shortcut = KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z);
+            let undo_shortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+            if ctx.input_mut(|i| i.consume_shortcut(&redo_shortcut)) {
+                self.redo();
+            } else if ctx.input_mut(|i| i.consume_shortcut(&undo_shortcut)) {
+                self.undo();
+            }
+        }
+
+        if let Ok((path, bytes)) = self.encounter_file_channel.1.try_recv() {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let index = self.battle_structure_index;
+            match self
+                .battle_structure_list
+                .get(index)
+                .ok_or_else(|| anyhow::anyhow!("Encounter {index} not found"))
+                .and_then(|target| read_encounter_file(&file_name, &bytes, target))
+            {
+                Ok(file) => {
+                    let source = match file.source_index {
+                        Some(source_index) => format!("encounter {source_index}"),
+                        None => "encounter".to_string(),
+                    };
+                    self.pending_paste = Some(PendingPaste {
+                        index,
+                        description: format!(
+                            "import {source} ({}) from {file_name}",
+                            file.stage_name()
+                        ),
+                        battle_structure: file.battle_structure,
+                        retarget: true,
+                        file: Some((file_name, bytes)),
+                    });
+                }
+                Err(err) => {
+                    execute(async move {
+                        error_dialog(&err.to_string()).await;
+                    });
+                }
+            }
+        }
+
+        if let Ok(folder) = self.stage_folder_channel.1.try_recv() {
+            self.settings.stage_folder = Some(folder);
+            self.stage_geometries.clear();
+        }
+
+        if let Ok(path) = self.preview_path_channel.1.try_recv() {
+            self.mod_info.preview_files.push(path);
+        }
+
+        if self.ffnx_export.is_some() {
+            ffnx_export_window(ctx, self);
+        }
+
+        if self.show_mod_package {
+            mod_package_window(ctx, self);
+        }
+
+        if self.code_import.is_some() {
+            code_import_window(ctx, self);
+        }
+
+        if self.relocation.is_some() {
+            relocation_window(ctx, self);
+        }
+
+        if self.pending_paste.is_some() {
+            paste_window(ctx, self);
+        }
+
+        if self.view == View::Table && !self.battle_structure_list.is_empty() {
+            if !ctx.wants_keyboard_input() {
+                table_clipboard(ctx, self);
+            }
+            egui::CentralPanel::default().show(ctx, |ui| table_contents(ui, self));
+            return;
+        }
+
+        if !ctx.wants_keyboard_input() && !self.battle_structure_list.is_empty() {
+            editor_clipboard(ctx, self);
+        }
+
+        if !self.battle_structure_list.is_empty() {
+            egui::SidePanel::left("encounter_browser")
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| encounter_browser_contents(ui, self));
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if !self.battle_structure_list.is_empty() {
+                egui::ScrollArea::vertical().show(ui, |ui| editor_contents(ui, self));
+            } else {
+                ui.centered_and_justified(|ui| {
+                    ui.heading("Open a scene.out file to start editing it");
+                });
+            }
+        });
+    }
+}
+
+
+fn editor_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    ui.heading("Battle Structure");
+    frame().show(ui, |ui| encounter_navigation_contents(ui, app));
+    ui.separator();
+
+    let stage_id = app
+        .battle_structure_list
+        .get(app.battle_structure_index)
+        .map(|battle_structure| battle_structure.stage_id)
+        .unwrap_or_default();
+    app.load_stage_geometry(stage_id);
+    let stage_geometry = app
+        .settings
+        .stage_folder
+        .as_ref()
+        .and_then(|_| app.stage_geometries.get(&stage_id));
+
+    let stage_bounds = match stage_geometry {
+        Some(Ok(geometry)) => Bounds::from_geometry(geometry),
+        _ => None,
+    };
+
+    let mut template_to_apply = None;
+    match app.battle_structure_list.get_mut(app.battle_structure_index) {
+        Some(battle_structure) => {
+            let before = battle_structure.clone();
+            let warnings = check_formation(
+                battle_structure,
+                app.overlap_radius,
+                stage_bounds.as_ref().unwrap_or(&DEFAULT_BOUNDS),
+            );
+            ui.heading("Stage");
+            frame().show(ui, |ui| stage_contents(ui, battle_structure));
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.heading("Enemies");
+                ui.checkbox(&mut app.settings.compact_enemies, "Compact layout");
+            });
+            frame().show(ui, |ui| {
+                if app.settings.compact_enemies {
+                    enemy_grid_contents(ui, battle_structure, &warnings, &mut app.bulk_set)
+                } else {
+                    enemies_contents(
+                        ui,
+                        battle_structure,
+                        &warnings,
+                        &mut app.enemy_selected_index,
+                    )
+                }
+            });
+            ui.separator();
+            ui.heading("Battlefield");
+            frame().show(ui, |ui| {
+                template_to_apply = formation_templates_contents(
+                    ui,
+                    battle_structure,
+                    &mut app.settings.formation_templates,
+                    &mut app.formation_template_index,
+                    &mut app.new_template_name,
+                );
+                formation_tools_contents(
+                    ui,
+                    battle_structure,
+                    app.enemy_selected_index,
+                    &mut app.formation_tools,
+                );
+                formation_warnings_contents(
+                    ui,
+                    &warnings,
+                    stage_bounds.is_some(),
+                    &mut app.overlap_radius,
+                );
+                battlefield_contents(
+                    ui,
+                    battle_structure,
+                    stage_geometry,
+                    &warnings,
+                    stage_bounds.as_ref().unwrap_or(&DEFAULT_BOUNDS),
+                    &mut app.map,
+                    &mut app.enemy_selected_index,
+                )
+            });
+
+            let pointer_down = ui.input(|i| i.pointer.any_down());
+            if *battle_structure != before {
+                let edit = Edit {
+                    index: app.battle_structure_index,
+                    before,
+                    after: battle_structure.clone(),
+                };
+                app.history.push_edit(edit, app.merging_edit);
+                app.merging_edit = pointer_down;
+            } else if !pointer_down {
+                app.merging_edit = false;
+            }
+        }
+        None => {
+            ui.heading("Battle structure not found!");
+        }
+    }
+
+    if let Some(template) = template_to_apply {
+        let index = app.battle_structure_index;
+        let after = template.apply(&app.battle_structure_list[index]);
+        let command = Command::from_changes(
+            &format!("Encounter {index}: apply formation {}", template.name),
+            &app.battle_structure_list,
+            [(index, after)],
+        );
+        app.history.execute(command, &mut app.battle_structure_list);
+    }
+}
+
+fn drop_overlay(ctx: &Context) {
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("drop_overlay"),
+    ));
+    let screen_rect = ctx.screen_rect();
+    painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(192));
+    painter.text(
+        screen_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "Drop the file to open it",
+        egui::TextStyle::Heading.resolve(&ctx.style()),
+        Color32::WHITE,
+    );
+}
+
+fn history_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    ui.heading("History");
+    ui.separator();
+
+    let mut undo_count = 0;
+    let mut redo_count = 0;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        let undo_commands = app.history.undo_commands();
+        for (i, command) in undo_commands.iter().enumerate() {
+            let is_current = i + 1 == undo_commands.len();
+            if command_contents(ui, command, Color32::PLACEHOLDER, is_current).clicked() {
+                undo_count = undo_commands.len() - i - 1;
+            }
+        }
+        for (i, command) in app.history.redo_commands().enumerate() {
+            if command_contents(ui, command, Color32::DARK_GRAY, false).clicked() {
+                redo_count = i + 1;
+            }
+        }
+    });
+
+    (0..undo_count).for_each(|_| app.undo());
+    (0..redo_count).for_each(|_| app.redo());
+}
+
+fn command_contents(
+    ui: &mut egui::Ui,
+    command: &Command,
+    text_color: Color32,
+    is_current: bool,
+) -> egui::Response {
+    let response = ui.selectable_label(
+        is_current,
+        egui::RichText::new(&command.label).color(text_color),
+    );
+    let changes: Vec<String> = command
+        .edits
+        .iter()
+        .flat_map(|edit| {
+            edit.changed_fields().into_iter().map(move |field| {
+                format!(
+                    "Encounter {}: {} {} -> {}",
+                    edit.index, field.name, field.old, field.new
+                )
+            })
+        })
+        .collect();
+    for change in changes.iter().take(HISTORY_CHANGES_SHOWN) {
+        ui.label(egui::RichText::new(change).small().color(text_color));
+    }
+    if changes.len() > HISTORY_CHANGES_SHOWN {
+        ui.label(
+            egui::RichText::new(format!(
+                "... and {} more",
+                changes.len() - HISTORY_CHANGES_SHOWN
+            ))
+            .small()
+            .color(text_color),
+        );
+    }
+    response
+}
+
+fn focused_index(command: &Command) -> Option<usize> {
+    match command.edits.as_slice() {
+        [edit] => Some(edit.index),
+        _ => None,
+    }
+}
+
+fn ffnx_export_window(ctx: &Context, app: &mut BattleStructureApp) {
+    let mut open = true;
+    let mut export_clicked = false;
+    if let Some(options) = app.ffnx_export.as_mut() {
+        egui::Window::new("Export for FFNx")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Language");
+                    ui.add(egui::TextEdit::singleline(&mut options.language).desired_width(40.0));
+                });
+                let language = options.language.trim();
+                let valid_language = language.is_empty() || is_language_code(language);
+                if !valid_language {
+                    ui.label(
+                        egui::RichText::new("Use two lowercase letters, such as fr")
+                            .color(DIRTY_COLOR),
+                    );
+                }
+                ui.checkbox(&mut options.write_manifest, "Write manifest of the changes");
+                ui.add_space(8.0);
+                export_clicked = ui
+                    .add_enabled(valid_language, egui::Button::new("Export..."))
+                    .clicked();
+            });
+    }
+
+    if export_clicked {
+        if let Some(options) = app.ffnx_export.take() {
+            export_ffnx(app, options);
+        }
+    } else if !open {
+        app.ffnx_export = None;
+    }
+}
+
+fn export_ffnx(app: &BattleStructureApp, options: FfnxExportOptions) {
+    let contents = match write_packed_battle_structure(&app.battle_structure_list) {
+        Ok(contents) => contents,
+        Err(err) => {
+            execute(async move {
+                error_dialog(&err.to_string()).await;
+            });
+            return;
+        }
+    };
+
+    let language = Some(options.language.trim().to_string()).filter(|code| !code.is_empty());
+    let manifest = options.write_manifest.then(|| {
+        let modified_from_vanilla = app.vanilla_baseline().map(|baseline| {
+            let indexes = (0..app.battle_structure_list.len())
+                .filter(|i| app.is_modified(*i))
+                .collect();
+            (baseline.name.as_str(), indexes)
+        });
+        manifest(
+            language.as_deref(),
+            &app.original_battle_structure_list,
+            &app.battle_structure_list,
+            modified_from_vanilla,
+        )
+    });
+
+    let task = AsyncFileDialog::new()
+        .set_title("Select the FF8 game folder or the mod folder")
+        .pick_folder();
+    execute(async move {
+        let Some(folder) = task.await else {
+            return;
+        };
+        if let Err(err) = export_direct(
+            folder.path(),
+            language.as_deref(),
+            &contents,
+            manifest.as_deref(),
+        ) {
+            error_dialog(&err.to_string()).await;
+        }
+    });
+}
+
+fn mod_package_window(ctx: &Context, app: &mut BattleStructureApp) {
+    let mut open = app.show_mod_package;
+    let mut export_as_archive: Option<bool> = None;
+    egui::Window::new("Package as Junction VIII mod")
+        .open(&mut open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            let info = &mut app.mod_info;
+            egui::Grid::new("mod_info_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut info.name);
+                    ui.end_row();
+                    ui.label("Author");
+                    ui.text_edit_singleline(&mut info.author);
+                    ui.end_row();
+                    ui.label("Version");
+                    ui.text_edit_singleline(&mut info.version);
+                    ui.end_row();
+                    ui.label("Category");
+                    ui.text_edit_singleline(&mut info.category);
+                    ui.end_row();
+                    ui.label("ID");
+                    ui.text_edit_singleline(&mut info.id);
+                    ui.end_row();
+                    ui.label("Description");
+                    ui.text_edit_multiline(&mut info.description);
+                    ui.end_row();
+                });
+
+            ui.add_space(8.0);
+            ui.label("Preview images");
+            let mut removed_index: Option<usize> = None;
+            for (i, path) in info.preview_files.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("x").clicked() {
+                        removed_index = Some(i);
+                    }
+                    ui.label(path.display().to_string());
+                });
+            }
+            if let Some(i) = removed_index {
+                info.preview_files.remove(i);
+            }
+            if ui.button("Add preview...").clicked() {
+                let sender = app.preview_path_channel.0.clone();
+                let task = AsyncFileDialog::new()
+                    .add_filter("Image", &["png", "jpg", "jpeg"])
+                    .pick_file();
+                let ctx = ui.ctx().clone();
+                execute(async move {
+                    if let Some(file) = task.await {
+                        let _ = sender.send(file.path().to_path_buf());
+                        ctx.request_repaint();
+                    }
+                });
+            }
+
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Export folder...").clicked() {
+                    export_as_archive = Some(false);
+                }
+                if ui.button("Export archive...").clicked() {
+                    export_as_archive = Some(true);
+                }
+            });
+        });
+    app.show_mod_package = open;
+
+    let Some(export_as_archive) = export_as_archive else {
+        return;
+    };
+    let contents = match write_packed_battle_structure(&app.battle_structure_list) {
+        Ok(contents) => contents,
+        Err(err) => {
+            execute(async move {
+                error_dialog(&err.to_string()).await;
+            });
+            return;
+        }
+    };
+    let info = app.mod_info.clone();
+    let language = app.opened_language();
+    app.show_mod_package = false;
+
+    if export_as_archive {
+        let task = AsyncFileDialog::new()
+            .add_filter("Archive", &["zip"])
+            .set_file_name(format!("{}.zip", info.name))
+            .save_file();
+        execute(async move {
+            let Some(file) = task.await else {
+                return;
+            };
+            if let Err(err) =
+                write_mod_archive(file.path(), &info, language.as_deref(), &contents)
+            {
+                error_dialog(&err.to_string()).await;
+            }
+        });
+    } else {
+        let task = AsyncFileDialog::new()
+            .set_title("Select the mod folder")
+            .pick_folder();
+        execute(async move {
+            let Some(folder) = task.await else {
+                return;
+            };
+            if let Err(err) =
+                write_mod_folder(folder.path(), &info, language.as_deref(), &contents)
+            {
+                error_dialog(&err.to_string()).await;
+            }
+        });
+    }
+}
+
+fn recent_files_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    ui.set_min_width(280.0);
+    if app.settings.recent_files.is_empty() {
+        ui.label("No recent files");
+    }
+
+    let mut selected_path: Option<PathBuf> = None;
+    for path in &app.settings.recent_files {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if ui
+            .button(file_name)
+            .on_hover_text(path.display().to_string())
+            .clicked()
+        {
+            selected_path = Some(path.clone());
+        }
+    }
+    if let Some(path) = selected_path {
+        if path.is_file() {
+            app.open_path(path, ui.ctx());
+        } else {
+            app.settings.remove_recent_file(&path);
+            execute(async move {
+                error_dialog(&format!("File not found: {}", path.display())).await;
+            });
+        }
+        ui.close_menu();
+    }
+
+    ui.separator();
+    ui.checkbox(&mut app.settings.reopen_last_file, "Reopen last file on startup");
+    if ui
+        .add_enabled(
+            !app.settings.recent_files.is_empty(),
+            egui::Button::new("Clear recent files"),
+        )
+        .clicked()
+    {
+        app.settings.recent_files.clear();
+        ui.close_menu();
+    }
+}
+
+fn installations_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    ui.set_min_width(280.0);
+    if app.installations.is_empty() {
+        ui.label("No FFVIII installation found");
+    }
+
+    let mut selected_path: Option<PathBuf> = None;
+    for installation in &app.installations {
+        ui.label(egui::RichText::new(installation.name()).strong())
+            .on_hover_text(installation.root.display().to_string());
+        for language in &installation.languages {
+            for path in &language.battle_files {
+                let file_name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let label = format!("[{}] {}", language.code, file_name);
+                let response = ui
+                    .add_enabled(is_scene_file(path), egui::Button::new(label))
+                    .on_hover_text(path.display().to_string())
+                    .on_disabled_hover_text("Archive files are not supported yet");
+                if response.clicked() {
+                    selected_path = Some(path.clone());
+                }
+            }
+        }
+        ui.separator();
+    }
+
+    if let Some(path) = selected_path {
+        app.open_path(path, ui.ctx());
+        ui.close_menu();
+    }
+
+    if ui.button("Rescan").clicked() {
+        app.scan_installations(ui.ctx());
+    }
+}
+
+fn encounter_navigation_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    ui.horizontal(|ui| {
+        ui.label(format!("Encounter ID: {}", app.battle_structure_index));
+        if app.is_modified(app.battle_structure_index) {
+            ui.label(egui::RichText::new("Modified").color(MODIFIED_COLOR));
+        }
+        if app.is_encounter_dirty(app.battle_structure_index) {
+            ui.label(egui::RichText::new("Unsaved").color(DIRTY_COLOR));
+        }
+    });
+
+    ui.horizontal(|ui| match app.vanilla_baseline() {
+        Some(baseline) => {
+            ui.label(format!("Vanilla baseline: {}", baseline.name));
+        }
+        None => {
+            ui.label("No vanilla baseline detected");
+        }
+    });
+}
+
+fn encounter_browser_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    ui.heading("Encounters");
+    ui.add(
+        egui::TextEdit::singleline(&mut app.encounter_filter.text)
+            .hint_text("Search stage or enemy...")
+            .desired_width(f32::INFINITY),
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("Jump to ID");
+        ui.add(egui::DragValue::new(&mut app.jump_to_id).range(0..=BATTLE_STRUCTURE_NUMBER - 1));
+        if ui.button("Go").clicked() {
+            app.battle_structure_index = app.jump_to_id;
+            app.scroll_to_selected = true;
+        }
+    });
+
+    ui.collapsing("Filters", |ui| encounter_filters_contents(ui, app));
+    ui.separator();
+
+    let indexes = app.listed_encounters();
+
+    // Arrow keys also move sliders, drag values and other focused widgets
+    let focused = ui.memory(|memory| memory.focused().is_some());
+    if !ui.ctx().wants_keyboard_input() && !focused {
+        let position = indexes
+            .iter()
+            .position(|i| *i == app.battle_structure_index);
+        let (up, down) = ui.input(|i| (i.key_pressed(Key::ArrowUp), i.key_pressed(Key::ArrowDown)));
+        let new_position = match position {
+            Some(position) if up => Some(position.saturating_sub(1)),
+            Some(position) if down => Some((position + 1).min(indexes.len().saturating_sub(1))),
+            None if up || down => Some(0),
+            _ => None,
+        };
+        if let Some(index) = new_position.and_then(|position| indexes.get(position)) {
+            app.battle_structure_index = *index;
+            app.scroll_to_selected = true;
+        }
+    }
+
+    ui.label(format!(
+        "{} of {} encounters",
+        indexes.len(),
+        app.battle_structure_list.len()
+    ));
+    let row_height = ui.spacing().interact_size.y;
+    egui::ScrollArea::vertical().auto_shrink(false).show_rows(
+        ui,
+        row_height,
+        indexes.len(),
+        |ui, rows| {
+            for i in indexes[rows].iter().copied() {
+                let summary = encounter_summary(i, &app.battle_structure_list[i]);
+                let mut text = egui::text::LayoutJob::default();
+                text.append(&summary, 0.0, egui::TextFormat::default());
+                append_encounter_markers(&mut text, app.is_modified(i), app.is_encounter_dirty(i));
+
+                let selected = i == app.battle_structure_index;
+                let response = ui.selectable_label(selected, text);
+                if response.clicked() {
+                    app.battle_structure_index = i;
+                }
+                if selected && app.scroll_to_selected {
+                    response.scroll_to_me(Some(egui::Align::Center));
+                    app.scroll_to_selected = false;
+                }
+            }
+        },
+    );
+}
+
+/// Appends the markers of an encounter that differs from the vanilla baseline or is unsaved
+fn append_encounter_markers(text: &mut egui::text::LayoutJob, modified: bool, dirty: bool) {
+    if modified {
+        text.append(
+            " M",
+            0.0,
+            egui::TextFormat::simple(egui::FontId::default(), MODIFIED_COLOR),
+        );
+    }
+    if dirty {
+        text.append(
+            " *",
+            0.0,
+            egui::TextFormat::simple(egui::FontId::default(), DIRTY_COLOR),
+        );
+    }
+}
+
+fn encounter_filters_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    let filter = &mut app.encounter_filter;
+    egui::ComboBox::from_label("Stage")
+        .width(160.0)
+        .selected_text(
+            filter
+                .stage_id
+                .and_then(|id| STAGE_NAMES.get(id as usize))
+                .copied()
+                .unwrap_or("Any"),
+        )
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut filter.stage_id, None, "Any");
+            (0..STAGE_NAMES.len()).for_each(|i| {
+                ui.selectable_value(&mut filter.stage_id, Some(i as u8), STAGE_NAMES[i]);
+            });
+        });
+    egui::ComboBox::from_label("Enemy")
+        .width(160.0)
+        .selected_text(
+            filter
+                .enemy_id
+                .and_then(|id| ENEMY_NAMES.get(id as usize))
+                .copied()
+                .unwrap_or("Any"),
+        )
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut filter.enemy_id, None, "Any");
+            (0..ENEMY_NAMES.len()).for_each(|i| {
+                ui.selectable_value(&mut filter.enemy_id, Some(i as u8), ENEMY_NAMES[i]);
+            });
+        });
+
+    ui.label("Required flags");
+    for (bit, name) in BATTLE_FLAG_NAMES.iter().enumerate() {
+        let mask = 1u8 << bit;
+        let mut required = filter.required_flags & mask > 0;
+        if ui.checkbox(&mut required, *name).changed() {
+            filter.required_flags ^= mask;
+        }
+    }
+
+    ui.separator();
+    ui.add_enabled(
+        app.vanilla_baseline().is_some(),
+        egui::Checkbox::new(&mut app.show_modified_only, "Modified from vanilla only"),
+    );
+    ui.checkbox(&mut app.show_dirty_only, "Unsaved only");
+    if ui.button("Reset filters").clicked() {
+        app.encounter_filter = EncounterFilter::default();
+        app.show_modified_only = false;
+        app.show_dirty_only = false;
+    }
+}
+
+fn table_contents(ui: &mut egui::Ui, app: &mut BattleStructureApp) {
+    let rows = app.table.rows(&app.battle_structure_list);
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} of {} encounters",
+            rows.len(),
+            app.battle_structure_list.len()
+        ));
+        if ui.button("Clear filters").clicked() {
+            app.table.filters.iter_mut().for_each(String::clear);
+        }
+        ui.label("Double-click a cell to edit it, Ctrl+C/Ctrl+V to copy and paste cells");
+    });
+    ui.separator();
+
+    let mut clicked_cell: Option<(usize, usize)> = None;
+    let mut edited: Option<(usize, BattleStructure)> = None;
+    let mut edit_error: Option<String> = None;
+    let shift = ui.input(|i| i.modifiers.shift);
+    let selected_ranges = app.table.selected_ranges();
+    let is_selected = |row: usize, column: usize| {
+        selected_ranges
+            .as_ref()
+            .is_some_and(|(rows, columns)| rows.contains(&row) && columns.contains(&column))
+    };
+    let TableState {
+        columns,
+        filters,
+        sort,
+        editing,
+        ..
+    } = &mut app.table;
+    let battle_structure_list = &app.battle_structure_list;
+    let saved_battle_structure_list = &app.saved_battle_structure_list;
+    let vanilla_baseline = app
+        .vanilla_baseline_index
+        .and_then(|i| app.vanilla_baselines.get(i));
+
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        let row_height = ui.spacing().interact_size.y;
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::exact(70.0))
+            .columns(
+                Column::initial(90.0).at_least(40.0).clip(true).resizable(true),
+                columns.len(),
+            )
+            .header(2.0 * row_height + 4.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("ID");
+                });
+                for (column, filter) in columns.iter().zip(filters.iter_mut()) {
+                    header.col(|ui| {
+                        ui.vertical(|ui| {
+                            let arrow = match *sort {
+                                Some((sorted, false)) if sorted == *column => " ^",
+                                Some((sorted, true)) if sorted == *column => " v",
+                                _ => "",
+                            };
+                            let response = ui
+                                .add(
+                                    egui::Label::new(
+                                        egui::RichText::new(format!("{}{arrow}", column.name()))
+                                            .strong(),
+                                    )
+                                    .sense(egui::Sense::click()),
+                                )
+                                .on_hover_text("Click to sort");
+                            if response.clicked() {
+                                *sort = match *sort {
+                                    Some((sorted, false)) if sorted == *column => {
+                                        Some((*column, true))
+                                    }
+                                    Some((sorted, true)) if sorted == *column => None,
+                                    _ => Some((*column, false)),
+                                };
+                            }
+                            ui.add(
+                                egui::TextEdit::singleline(filter)
+                                    .hint_text("Filter")
+                                    .desired_width(f32::INFINITY),
+                            )
+                            .on_hover_text("Name or value, or a comparison such as >= 50");
+                        });
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, rows.len(), |mut row| {
+                    let row_position = row.index();
+                    let index = rows[row_position];
+                    let battle_structure = &battle_structure_list[index];
+                    row.col(|ui| {
+                        let mut text = egui::text::LayoutJob::default();
+                        text.append(&index.to_string(), 0.0, egui::TextFormat::default());
+                        let modified = vanilla_baseline
+                            .is_some_and(|baseline| baseline.is_modified(index, battle_structure));
+                        let dirty =
+                            saved_battle_structure_list.get(index) != Some(battle_structure);
+                        append_encounter_markers(&mut text, modified, dirty);
+                        ui.label(text);
+                    });
+                    for (column_position, column) in columns.iter().enumerate() {
+                        row.col(|ui| {
+                            let selected = is_selected(row_position, column_position);
+                            match editing {
+                                Some((editing_index, editing_column, text))
+                                    if *editing_index == index && editing_column == column =>
+                                {
+                                    let response = ui.add(
+                                        egui::TextEdit::singleline(text)
+                                            .desired_width(f32::INFINITY),
+                                    );
+                                    response.request_focus();
+                                    if response.lost_focus() {
+                                        if ui.input(|i| i.key_pressed(Key::Enter)) {
+                                            let mut after = battle_structure.clone();
+                                            match column.set(&mut after, text) {
+                                                Ok(()) => edited = Some((index, after)),
+                                                Err(err) => edit_error = Some(err.to_string()),
+                                            }
+                                        }
+                                        *editing = None;
+                                    }
+                                }
+                                _ if column.is_bool() => {
+                                    let mut value = column.value(battle_structure) > 0;
+                                    let response = ui.checkbox(&mut value, "");
+                                    if response.changed() {
+                                        let mut after = battle_structure.clone();
+                                        if column.set(&mut after, &value.to_string()).is_ok() {
+                                            edited = Some((index, after));
+                                        }
+                                    }
+                                    if response.clicked() {
+                                        clicked_cell = Some((row_position, column_position));
+                                    }
+                                    if selected {
+                                        ui.painter().rect_stroke(
+                                            ui.max_rect(),
+                                            0.0,
+                                            ui.visuals().selection.stroke,
+                                        );
+                                    }
+                                }
+                                _ => {
+                                    let text = match column.description(battle_structure) {
+                                        Some(name) => {
+                                            format!("{} {name}", column.text(battle_structure))
+                                        }
+                                        None => column.text(battle_structure),
+                                    };
+                                    let response = ui.selectable_label(selected, text);
+                                    if response.clicked() {
+                                        clicked_cell = Some((row_position, column_position));
+                                    }
+                                    if response.double_clicked() {
+                                        *editing = Some((
+                                            index,
+                                            *column,
+                                            column.text(battle_structure),
+                                        ));
+                                    }
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+    });
+
+    if let Some(cell) = clicked_cell {
+        let anchor = match app.table.selection {
+            Some((anchor, _)) if shift => anchor,
+            _ => cell,
+        };
+        app.table.selection = Some((anchor, cell));
+        app.battle_structure_index = rows[cell.0];
+    }
+    if let Some((index, after)) = edited {
+        let before = app.battle_structure_list[index].clone();
+        app.history.execute(
+            Command::from_edit(Edit {
+                index,
+                before,
+                after,
+            }),
+            &mut app.battle_structure_list,
+        );
+    }
+    if let Some(err) = edit_error {
+        execute(async move {
+            error_dialog(&err).await;
+        });
+    }
+}
+
+/// Copies the selected encounter, or previews the pasted encounter or enemy slot
+fn editor_clipboard(ctx: &Context, app: &mut BattleStructureApp) {
+    let index = app.battle_structure_index;
+    let Some(battle_structure) = app.battle_structure_list.get(index) else {
+        return;
+    };
+    let events = ctx.input(|i| i.events.clone());
+    for event in events {
+        match event {
+            egui::Event::Copy => ctx.copy_text(copy_encounter(battle_structure)),
+            egui::Event::Paste(text) => {
+                let pasted = if is_encounter_code(&text) {
+                    decode_encounter(&text).map(|battle_structure| Paste {
+                        kind: PasteKind::Encounter,
+                        battle_structure,
+                    })
+                } else {
+                    paste(&text, battle_structure, app.enemy_selected_index)
+                };
+                match pasted {
+                    Ok(paste) => {
+                        let description = match paste.kind {
+                            PasteKind::Encounter => "paste encounter".to_string(),
+                            PasteKind::EnemySlot(slot) => format!("paste enemy slot {slot}"),
+                        };
+                        app.pending_paste = Some(PendingPaste {
+                            index,
+                            description,
+                            battle_structure: paste.battle_structure,
+                            retarget: false,
+                            file: None,
+                        });
+                    }
+                    Err(err) => {
+                        execute(async move {
+                            error_dialog(&err.to_string()).await;
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Decodes an encounter code into the selected encounter, previewed as a paste
+fn code_import_window(ctx: &Context, app: &mut BattleStructureApp) {
+    let mut open = true;
+    let mut import_clicked = false;
+    if let Some(code) = app.code_import.as_mut() {
+        egui::Window::new("Import code")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Encounter code to import into encounter {}",
+                    app.battle_structure_index
+                ));
+                ui.add(
+                    egui::TextEdit::multiline(code)
+                        .hint_text("FF8E1-...")
+                        .desired_rows(4)
+                        .desired_width(360.0),
+                );
+                ui.add_space(8.0);
+                import_clicked = ui
+                    .add_enabled(!code.trim().is_empty(), egui::Button::new("Import"))
+                    .clicked();
+            });
+    }
+
+    if import_clicked {
+        let code = app.code_import.take().unwrap_or_default();
+        match decode_encounter(&code) {
+            Ok(battle_structure) => {
+                app.pending_paste = Some(PendingPaste {
+                    index: app.battle_structure_index,
+                    description: "import encounter code".to_string(),
+                    battle_structure,
+                    retarget: true,
+                    file: None,
+                });
+            }
+            Err(err) => {
+                app.code_import = Some(code);
+                execute(async move {
+                    error_dialog(&err.to_string()).await;
+                });
+            }
+        }
+    } else if !open {
+        app.code_import = None;
+    }
+}
+
+/// Copies or moves the selected encounter to another ID, warning when the target is in use
+fn relocation_window(ctx: &Context, app: &mut BattleStructureApp) {
+    let from = app.battle_structure_index;
+    let last_index = app.battle_structure_list.len().saturating_sub(1);
+    let mut open = true;
+    let mut relocate_clicked = false;
+    let mut save_report_clicked = false;
+    if let Some((kind, target)) = app.relocation.as_mut() {
+        egui::Window::new(format!("{} encounter", kind.name()))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} encounter {from} to", kind.name()));
+                    ui.add(egui::DragValue::new(target).range(0..=last_index));
+                    if ui.button("Next free").clicked() {
+                        if let Some(free) = next_free(&app.battle_structure_list, *target) {
+                            *target = free;
+                        }
+                    }
+                });
+                if *kind == RelocationKind::Move {
+                    ui.label(format!("The enemy slots of encounter {from} will be disabled."));
+                }
+
+                let target_structure = &app.battle_structure_list[*target];
+                let target_free = is_free(target_structure);
+                if *target == from {
+                    ui.label(
+                        egui::RichText::new("Choose another encounter ID.").color(DIRTY_COLOR),
+                    );
+                } else if !target_free {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "⚠ Encounter {target} is not free, it will be overwritten:\n{}",
+                            encounter_summary(*target, target_structure)
+                        ))
+                        .color(DIRTY_COLOR),
+                    );
+                }
+                ui.add_space(8.0);
+                let button = match target_free {
+                    true => kind.name().to_string(),
+                    false => format!("Overwrite and {}", kind.name().to_lowercase()),
+                };
+                relocate_clicked = ui
+                    .add_enabled(*target != from, egui::Button::new(button))
+                    .clicked();
+
+                ui.separator();
+                let relocation_count = app.relocations.applied().len();
+                ui.label(format!("Relocations to report: {relocation_count}"));
+                ui.label(
+                    egui::RichText::new(
+                        "Encounter IDs used by other game files are not loaded by the editor, \
+                         update them from the report.",
+                    )
+                    .small(),
+                );
+                save_report_clicked = ui
+                    .add_enabled(relocation_count > 0, egui::Button::new("Save report..."))
+                    .on_hover_text("Also in File > Save relocation report...")
+                    .clicked();
+            });
+    }
+
+    if save_report_clicked {
+        app.save_relocation_report();
+    }
+
+    if relocate_clicked {
+        if let Some((kind, target)) = app.relocation {
+            match relocate(&app.battle_structure_list, kind, from, target) {
+                Ok((relocation, changes)) => {
+                    let command = Command::from_changes(
+                        &format!("{} encounter {from} to {target}", kind.name()),
+                        &app.battle_structure_list,
+                        changes,
+                    );
+                    if !command.is_empty() {
+                        app.relocations.record(relocation, command.clone());
+                    }
+                    app.history.execute(command, &mut app.battle_structure_list);
+                    app.battle_structure_index = target;
+                    app.relocation = None;
+                }
+                Err(err) => {
+                    execute(async move {
+                        error_dialog(&err.to_string()).await;
+                    });
+                }
+            }
+        }
+    } else if !open {
+        app.relocation = None;
+    }
+}
+
+/// Lists the fields overwritten by the pending paste before applying it as a single command
+fn paste_window(ctx: &Context, app: &mut BattleStructureApp) {
+    let last_index = app.battle_structure_list.len().saturating_sub(1);
+    let Some(pending) = app.pending_paste.as_mut() else {
+        return;
+    };
+    let Some(before) = app.battle_structure_list.get(pending.index) else {
+        app.pending_paste = None;
+        return;
+    };
+    let label = format!("Encounter {}: {}", pending.index, pending.description);
+    let changes = before.changed_fields(&pending.battle_structure);
+    let previous_index = pending.index;
+
+    let mut open = true;
+    let mut paste_clicked = false;
+    let mut cancel_clicked = false;
+    egui::Window::new("Paste")
+        .open(&mut open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            if pending.retarget {
+                ui.horizontal(|ui| {
+                    ui.label("Replace encounter");
+                    ui.add(egui::DragValue::new(&mut pending.index).range(0..=last_index));
+                    ui.label(format!("with {}", pending.description));
+                });
+            } else {
+                ui.label(&label);
+            }
+            ui.add_space(4.0);
+            if changes.is_empty() {
+                ui.label("Nothing would change.");
+            } else {
+                ui.label(format!("{} fields will be overwritten:", changes.len()));
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for change in &changes {
+                            ui.label(format!("{}: {} -> {}", change.name, change.old, change.new));
+                        }
+                    });
+            }
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                paste_clicked = ui
+                    .add_enabled(!changes.is_empty(), egui::Button::new("Paste"))
+                    .clicked();
+                cancel_clicked = ui.button("Cancel").clicked();
+            });
+        });
+
+    if let Some(pending) = app.pending_paste.as_mut() {
+        if let (true, Some((file_name, bytes))) = (pending.index != previous_index, &pending.file) {
+            let target = &app.battle_structure_list[pending.index];
+            match read_encounter_file(file_name, bytes, target) {
+                Ok(file) => pending.battle_structure = file.battle_structure,
                 Err(err) => {
+                    app.pending_paste = None;
                     execute(async move {
                         error_dialog(&err.to_string()).await;
                     });
+                    return;
                 }
             }
         }
+    }
 
-        egui::TopBottomPanel::top("app_top_bar")
-            .frame(egui::Frame::none().inner_margin(4.0))
-            .show(ctx, |ui| {
-                egui::menu::bar(ui, |ui| {
-                    ui.menu_button("File", |ui| {
-                        ui.set_max_width(200.0);
+    if paste_clicked {
+        if let Some(pending) = app.pending_paste.take() {
+            let command = Command::from_changes(
+                &label,
+                &app.battle_structure_list,
+                [(pending.index, pending.battle_structure)],
+            );
+            app.history.execute(command, &mut app.battle_structure_list);
+        }
+    } else if cancel_clicked || !open {
+        app.pending_paste = None;
+    }
+}
 
-                        if ui.button("Open...").clicked() {
-                            let sender = self.file_bytes_channel.0.clone();
-                            let task = AsyncFileDialog::new()
-                                .set_title("Select scene.out file")
-                                .add_filter("scene.out", &["out"])
-                                .set_directory(".")
-                                .pick_file();
-                            let ctx = ui.ctx().clone();
-                            execute(async move {
-                                let file = task.await;
-                                if let Some(file) = file {
-                                    let bytes = file.read().await;
-                                    let _ = sender.send(bytes);
-                                    ctx.request_repaint();
-                                }
-                            });
-                            ui.close_menu();
-                        }
+/// Copies the selected cells, or pastes the clipboard from the first selected cell. A single
+/// pasted value fills the whole selection.
+fn table_clipboard(ctx: &Context, app: &mut BattleStructureApp) {
+    let Some((selected_rows, selected_columns)) = app.table.selected_ranges() else {
+        return;
+    };
+    let events = ctx.input(|i| i.events.clone());
+    for event in events {
+        let rows = app.table.rows(&app.battle_structure_list);
+        let rows = &rows[(*selected_rows.start()).min(rows.len())..];
+        match event {
+            egui::Event::Copy => {
+                let rows = &rows[..selected_rows.clone().count().min(rows.len())];
+                let columns = &app.table.columns[selected_columns.clone()];
+                ctx.copy_text(copy_cells(&app.battle_structure_list, rows, columns));
+            }
+            egui::Event::Paste(text) => {
+                let text = text.trim_end_matches(['\r', '\n']);
+                let text = if text.contains(['\t', '\n']) {
+                    text.to_string()
+                } else {
+                    let line = vec![text; selected_columns.clone().count()].join("\t");
+                    vec![line; selected_rows.clone().count()].join("\n")
+                };
+                match paste_cells(
+                    &app.battle_structure_list,
+                    rows,
+                    &app.table.columns,
+                    (0, *selected_columns.start()),
+                    &text,
+                ) {
+                    Ok(changes) => {
+                        let command = Command::from_changes(
+                            &format!("Paste into {} encounters", changes.len()),
+                            &app.battle_structure_list,
+                            changes,
+                        );
+                        app.history.execute(command, &mut app.battle_structure_list);
+                    }
+                    Err(err) => {
+                        execute(async move {
+                            error_dialog(&err.to_string()).await;
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
 
-                        let save_as_enabled = !self.battle_structure_list.is_empty();
-                        if ui
-                            .add_enabled(save_as_enabled, egui::Button::new("Save as..."))
-                            .clicked()
-                        {
-                            let task = rfd::AsyncFileDialog::new().save_file();
-                            match write_packed_battle_structure(&self.battle_structure_list) {
-                                Ok(contents) => {
-                                    execute(async move {
-                                        let file = task.await;
-                                        if let Some(file) = file {
-                                            _ = file.write(contents.as_ref()).await;
-                                        }
-                                    });
-                                }
-                                Err(err) => {
-                                    execute(async move {
-                                        error_dialog(&err.to_string()).await;
-                                    });
-                                }
-                            };
-                            ui.close_menu();
-                        }
-                    });
-                });
+/// Lets the user pick, apply, save and delete formation templates. Returns the template to
+/// apply, which is done as a single command.
+fn formation_templates_contents(
+    ui: &mut egui::Ui,
+    battle_structure: &BattleStructure,
+    saved_templates: &mut Vec<FormationTemplate>,
+    template_index: &mut usize,
+    new_template_name: &mut String,
+) -> Option<FormationTemplate> {
+    let builtin = builtin_templates();
+    let builtin_count = builtin.len();
+    let templates: Vec<FormationTemplate> =
+        builtin.into_iter().chain(saved_templates.iter().cloned()).collect();
+    *template_index = (*template_index).min(templates.len() - 1);
+
+    let mut template_to_apply = None;
+    let mut delete_clicked = false;
+    ui.horizontal(|ui| {
+        ui.label("Formation");
+        egui::ComboBox::from_id_source("formation_template")
+            .selected_text(&templates[*template_index].name)
+            .show_ui(ui, |ui| {
+                for (i, template) in templates.iter().enumerate() {
+                    let label = if i < builtin_count {
+                        format!("{} ({} slots)", template.name, template.positions.len())
+                    } else {
+                        format!("{} ({} slots, saved)", template.name, template.positions.len())
+                    };
+                    ui.selectable_value(template_index, i, label);
+                }
             });
+        if ui
+            .button("Apply")
+            .on_hover_text("Moves the enemies, keeping their ids and levels")
+            .clicked()
+        {
+            template_to_apply = Some(templates[*template_index].clone());
+        }
+        delete_clicked = ui
+            .add_enabled(*template_index >= builtin_count, egui::Button::new("Delete"))
+            .clicked();
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if !self.battle_structure_list.is_empty() {
-                ui.heading("Battle Structure");
-                frame().show(ui, |ui| {
-                    ui.label("Encounter ID");
-                    ui.add(egui::Slider::new(
-                        &mut self.battle_structure_index,
-                        0..=BATTLE_STRUCTURE_NUMBER - 1,
-                    ));
-                });
-                ui.separator();
+        ui.separator();
+        ui.add(
+            egui::TextEdit::singleline(new_template_name)
+                .hint_text("Template name")
+                .desired_width(120.0),
+        );
+        let can_save = !new_template_name.trim().is_empty()
+            && battle_structure.enemies.iter().any(|enemy| enemy.enabled);
+        if ui
+            .add_enabled(can_save, egui::Button::new("Save current"))
+            .on_hover_text("Saves the positions of the enabled enemies as a template")
+            .clicked()
+        {
+            saved_templates.push(FormationTemplate::from_battle_structure(
+                new_template_name.trim(),
+                battle_structure,
+            ));
+            *template_index = builtin_count + saved_templates.len() - 1;
+            new_template_name.clear();
+        }
+    });
 
-                match self
-                    .battle_structure_list
-                    .get_mut(self.battle_structure_index)
-                {
-                    Some(battle_structure) => {
-                        ui.heading("Stage");
-                        frame().show(ui, |ui| stage_contents(ui, battle_structure));
-                        ui.separator();
-                        ui.heading("Enemies");
-                        frame().show(ui, |ui| {
-                            enemies_contents(ui, battle_structure, &mut self.enemy_selected_index)
-                        });
-                        ui.separator();
-                    }
-                    None => {
-                        ui.heading("Battle structure not found!");
-                    }
+    if delete_clicked {
+        saved_templates.remove(*template_index - builtin_count);
+        *template_index -= 1;
+    }
+    template_to_apply
+}
+
+/// Buttons moving the selected enemy slots in place, with the parameters of the tools
+fn formation_tools_contents(
+    ui: &mut egui::Ui,
+    battle_structure: &mut BattleStructure,
+    selected_slot: usize,
+    options: &mut FormationToolOptions,
+) {
+    if let SlotSelection::Selected(slot) = &mut options.selection {
+        *slot = selected_slot;
+    }
+
+    let mut tool = None;
+    ui.horizontal(|ui| {
+        ui.label("Tools on");
+        egui::ComboBox::from_id_source("formation_tools_selection")
+            .selected_text(options.selection.name())
+            .show_ui(ui, |ui| {
+                for selection in [
+                    SlotSelection::All,
+                    SlotSelection::Enabled,
+                    SlotSelection::Selected(selected_slot),
+                ] {
+                    ui.selectable_value(&mut options.selection, selection, selection.name());
                 }
-            } else {
-                ui.centered_and_justified(|ui| {
-                    ui.heading("Open a scene.out file to start editing it");
-                });
-            }
+            });
+        if ui.button(FormationTool::Center.name()).clicked() {
+            tool = Some(FormationTool::Center);
+        }
+        if ui.button(FormationTool::Mirror.name()).clicked() {
+            tool = Some(FormationTool::Mirror);
+        }
+    });
+    ui.horizontal(|ui| {
+        let distribute = FormationTool::DistributeX {
+            spacing: options.spacing,
+        };
+        if ui.button(distribute.name()).clicked() {
+            tool = Some(distribute);
+        }
+        ui.label("spacing");
+        ui.add(egui::DragValue::new(&mut options.spacing).range(0..=i16::MAX));
+        ui.separator();
+
+        let scale = FormationTool::Scale {
+            factor: options.factor,
+        };
+        if ui.button(scale.name()).clicked() {
+            tool = Some(scale);
+        }
+        ui.label("by");
+        ui.add(
+            egui::DragValue::new(&mut options.factor)
+                .range(0.0..=10.0)
+                .speed(0.05)
+                .suffix("x"),
+        );
+        ui.separator();
+
+        let nudge = FormationTool::Nudge {
+            offset: options.offset,
+        };
+        if ui.button(nudge.name()).clicked() {
+            tool = Some(nudge);
+        }
+        for (axis, value) in ["X", "Y", "Z"].into_iter().zip(options.offset.iter_mut()) {
+            ui.label(axis);
+            ui.add(egui::DragValue::new(value));
+        }
+    });
+
+    if let Some(tool) = tool {
+        tool.apply(battle_structure, options.selection);
+    }
+}
+
+fn formation_warnings_contents(
+    ui: &mut egui::Ui,
+    warnings: &[FormationWarning],
+    has_stage_bounds: bool,
+    overlap_radius: &mut f32,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Overlap radius");
+        ui.add(
+            egui::DragValue::new(overlap_radius)
+                .range(0.0..=5000.0)
+                .speed(10.0)
+                .suffix(" units"),
+        );
+        ui.label(if has_stage_bounds {
+            "Playable area from the stage floor"
+        } else {
+            "Default playable area"
+        });
+    });
+    for warning in warnings {
+        ui.label(egui::RichText::new(format!("⚠ {}", warning.message())).color(DIRTY_COLOR));
+    }
+}
+
+/// Warnings of the enemy slot, shown next to it
+fn slot_warnings(warnings: &[FormationWarning], slot: usize) -> Vec<String> {
+    warnings
+        .iter()
+        .filter(|warning| warning.slot == slot)
+        .map(|warning| warning.message())
+        .collect()
+}
+
+fn slot_warning_contents(ui: &mut egui::Ui, warnings: &[FormationWarning], slot: usize) {
+    let messages = slot_warnings(warnings, slot);
+    if !messages.is_empty() {
+        ui.label(egui::RichText::new("⚠").color(DIRTY_COLOR))
+            .on_hover_text(messages.join("\n"));
+    }
+}
+
+fn battlefield_contents(
+    ui: &mut egui::Ui,
+    battle_structure: &mut BattleStructure,
+    stage_geometry: Option<&Result<StageGeometry, String>>,
+    warnings: &[FormationWarning],
+    bounds: &Bounds,
+    map: &mut MapState,
+    enemy_selected_index: &mut usize,
+) {
+    let mut fit = false;
+    ui.horizontal(|ui| {
+        for projection in Projection::ALL {
+            fit |= ui
+                .selectable_value(&mut map.projection, projection, projection.name())
+                .changed();
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut map.snap, "Snap to grid");
+        ui.add(
+            egui::DragValue::new(&mut map.grid_size)
+                .range(10..=1000)
+                .suffix(" units"),
+        );
+        fit |= ui.button("Fit enemies").clicked();
+        if ui.button("Reset view").clicked() {
+            map.view = MapView::default();
+        }
+        ui.label(match map.projection {
+            Projection::TopDown => "Drag enemies to move them",
+            Projection::Front | Projection::Side => "Drag enemies to change their height",
         });
+        ui.label("Drag the background to pan, Ctrl+scroll to zoom");
+    });
+
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width(), MAP_HEIGHT),
+        egui::Sense::click_and_drag(),
+    );
+    let rect = response.rect;
+    let painter = painter.with_clip_rect(rect);
+    let enemies = &mut battle_structure.enemies;
+    let projection = map.projection;
+
+    if fit {
+        let positions: Vec<[f32; 2]> = enemies
+            .iter()
+            .filter(|enemy| enemy.enabled)
+            .map(|enemy| projection.position(enemy))
+            .chain([projection.project(PARTY_POSITION)])
+            .collect();
+        map.view.fit(&positions, rect.size().into(), 2.0 * MAP_MARKER_RADIUS);
+    }
+
+    let pointer_offset = response
+        .interact_pointer_pos()
+        .or(response.hover_pos())
+        .map(|pos| <[f32; 2]>::from(pos - rect.center()));
+    let grid_size = map.snap.then_some(map.grid_size);
+    if let Some(offset) = pointer_offset {
+        let zoom = ui.input(|i| i.zoom_delta());
+        if response.hovered() && zoom != 1.0 {
+            map.view.zoom_at(offset, zoom);
+        }
+
+        let hit_radius = MAP_MARKER_RADIUS / map.view.zoom;
+        let enemy_under_pointer =
+            projection.enemy_at(enemies, map.view.to_battlefield(offset), hit_radius);
+        if response.drag_started() {
+            map.dragging = enemy_under_pointer;
+        }
+        if response.drag_started() || response.clicked() {
+            if let Some(slot) = enemy_under_pointer {
+                *enemy_selected_index = slot;
+            }
+        }
+        if response.dragged() {
+            match map.dragging.and_then(|slot| enemies.get_mut(slot)) {
+                Some(enemy) => {
+                    projection.move_enemy(enemy, map.view.to_battlefield(offset), grid_size)
+                }
+                None => map.view.pan(response.drag_delta().into()),
+            }
+        }
+    }
+    if response.drag_stopped() {
+        map.dragging = None;
+    }
+
+    let to_screen = |point: [f32; 2]| rect.center() + egui::Vec2::from(map.view.to_screen(point));
+    painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+
+    // grid lines, coarser when zoomed out so that they stay apart
+    let mut spacing = map.grid_size.max(1) as f32;
+    while spacing * map.view.zoom < 8.0 {
+        spacing *= 10.0;
+    }
+    let top_left = map.view.to_battlefield((rect.min - rect.center()).into());
+    let bottom_right = map.view.to_battlefield((rect.max - rect.center()).into());
+    let grid_stroke = egui::Stroke::new(1.0, ui.visuals().faint_bg_color);
+    let axis_stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+    let mut x = (top_left[0] / spacing).floor() * spacing;
+    while x <= bottom_right[0] {
+        let screen_x = to_screen([x, 0.0]).x;
+        let stroke = if x == 0.0 { axis_stroke } else { grid_stroke };
+        painter.vline(screen_x, rect.y_range(), stroke);
+        x += spacing;
+    }
+    let mut z = (top_left[1] / spacing).floor() * spacing;
+    while z <= bottom_right[1] {
+        let screen_y = to_screen([0.0, z]).y;
+        let stroke = if z == 0.0 { axis_stroke } else { grid_stroke };
+        painter.hline(rect.x_range(), screen_y, stroke);
+        z += spacing;
+    }
+
+    if let Some(Ok(geometry)) = stage_geometry {
+        let fill = ui.visuals().text_color().gamma_multiply(0.04);
+        let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.5));
+        for polygon in &geometry.polygons {
+            let points = polygon
+                .iter()
+                .map(|vertex| to_screen(projection.project(vertex.map(f32::from))))
+                .collect();
+            painter.add(egui::Shape::convex_polygon(points, fill, stroke));
+        }
+    }
+
+    if projection == Projection::TopDown {
+        let bounds_rect = egui::Rect::from_two_pos(
+            to_screen(bounds.min.map(f32::from)),
+            to_screen(bounds.max.map(f32::from)),
+        );
+        painter.rect_stroke(
+            bounds_rect,
+            0.0,
+            egui::Stroke::new(1.0, DIRTY_COLOR.gamma_multiply(0.5)),
+        );
+    }
+
+    let party = to_screen(projection.project(PARTY_POSITION));
+    painter.rect_filled(
+        egui::Rect::from_center_size(party, egui::vec2(60.0, 14.0)),
+        2.0,
+        PARTY_COLOR.gamma_multiply(0.5),
+    );
+    painter.text(
+        party,
+        egui::Align2::CENTER_CENTER,
+        "Party",
+        egui::FontId::proportional(11.0),
+        ui.visuals().strong_text_color(),
+    );
+
+    // disabled enemies are drawn first so that the enabled ones stay on top
+    let mut slots: Vec<usize> = (0..enemies.len()).collect();
+    slots.sort_by_key(|slot| enemies[*slot].enabled);
+    for slot in slots {
+        let enemy = &enemies[slot];
+        let center = to_screen(projection.position(enemy));
+        let (fill, stroke_color) = match SlotState::of(enemy) {
+            SlotState::Enabled => (ENABLED_COLOR, ENABLED_COLOR),
+            SlotState::Hidden => (Color32::TRANSPARENT, ENABLED_COLOR),
+            SlotState::NotLoaded => (NOT_LOADED_COLOR, NOT_LOADED_COLOR),
+            SlotState::Disabled => (Color32::TRANSPARENT, Color32::DARK_GRAY),
+        };
+        painter.circle(
+            center,
+            MAP_MARKER_RADIUS,
+            fill,
+            egui::Stroke::new(2.0, stroke_color),
+        );
+        if slot == *enemy_selected_index {
+            painter.circle_stroke(
+                center,
+                MAP_MARKER_RADIUS + 3.0,
+                ui.visuals().selection.stroke,
+            );
+        }
+        if warnings.iter().any(|warning| warning.slot == slot) {
+            painter.circle_stroke(
+                center,
+                MAP_MARKER_RADIUS + 6.0,
+                egui::Stroke::new(2.0, DIRTY_COLOR),
+            );
+        }
+        painter.text(
+            center,
+            egui::Align2::CENTER_CENTER,
+            slot.to_string(),
+            egui::FontId::monospace(11.0),
+            ui.visuals().strong_text_color(),
+        );
+        if enemy.enabled {
+            painter.text(
+                center + egui::vec2(MAP_MARKER_RADIUS + 4.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                *ENEMY_NAMES
+                    .get(enemy.id as usize)
+                    .unwrap_or(&"Invalid enemy id!"),
+                egui::FontId::proportional(11.0),
+                ui.visuals().text_color(),
+            );
+        }
     }
+
+    ui.horizontal(|ui| {
+        for (state, color) in [
+            ("● Enabled", ENABLED_COLOR),
+            ("○ Hidden", ENABLED_COLOR),
+            ("● Not loaded", NOT_LOADED_COLOR),
+            ("○ Disabled", Color32::DARK_GRAY),
+        ] {
+            ui.label(egui::RichText::new(state).color(color));
+        }
+        ui.separator();
+        match stage_geometry {
+            Some(Ok(_)) => {}
+            Some(Err(err)) => {
+                ui.label(egui::RichText::new(err).small().color(DIRTY_COLOR));
+            }
+            None => {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Set View > Stage geometry folder to draw {}",
+                        stage_file_name(battle_structure.stage_id)
+                    ))
+                    .small(),
+                );
+            }
+        }
+        if let Some(enemy) = enemies.get(*enemy_selected_index) {
+            ui.separator();
+            ui.label(format!(
+                "Slot {}: x {}, y {}, z {}",
+                enemy_selected_index, enemy.coordinate.x, enemy.coordinate.y, enemy.coordinate.z
+            ));
+        }
+    });
 }
 
 fn stage_contents(ui: &mut egui::Ui, battle_structure: &mut BattleStructure) {
@@ -227,8 +2948,11 @@ fn stage_contents(ui: &mut egui::Ui, battle_structure: &mut BattleStructure) {
 fn enemies_contents(
     ui: &mut egui::Ui,
     battle_structure: &mut BattleStructure,
+    warnings: &[FormationWarning],
     enemy_selected_index: &mut usize,
 ) {
+    let mut reorder = None;
+    let slot_count = battle_structure.enemies.len();
     ui.columns(2, |cols| {
         cols[0].vertical(|ui| {
             for i in 0..battle_structure.enemies.len() {
@@ -252,12 +2976,62 @@ fn enemies_contents(
                     Color32::DARK_GRAY
                 };
 
-                ui.selectable_value(
-                    enemy_selected_index,
-                    i,
-                    egui::RichText::new(enemy_name).color(text_color),
-                );
+                let row = ui.horizontal(|ui| {
+                    ui.dnd_drag_source(egui::Id::new(("enemy_slot_drag", i)), DraggedSlot(i), |ui| {
+                        ui.label("☰");
+                    })
+                    .response
+                    .on_hover_text("Drag to move the slot");
+                    ui.selectable_value(
+                        enemy_selected_index,
+                        i,
+                        egui::RichText::new(enemy_name).color(text_color),
+                    )
+                    .context_menu(|ui| {
+                        ui.menu_button("Swap with", |ui| {
+                            for other in (0..slot_count).filter(|other| *other != i) {
+                                if ui.button(format!("Slot {other}")).clicked() {
+                                    reorder = Some(SlotReorder::Swap(i, other));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                    slot_warning_contents(ui, warnings, i);
+                });
+                let row = row.response;
+                if let Some(dragged) = row.dnd_hover_payload::<DraggedSlot>() {
+                    if dragged.0 != i {
+                        let y = if dragged.0 < i {
+                            row.rect.bottom()
+                        } else {
+                            row.rect.top()
+                        };
+                        let stroke = ui.visuals().selection.stroke;
+                        ui.painter().hline(row.rect.x_range(), y, stroke);
+                    }
+                }
+                if let Some(dragged) = row.dnd_release_payload::<DraggedSlot>() {
+                    reorder = Some(SlotReorder::Move(dragged.0, i));
+                }
             }
+
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                let selected = *enemy_selected_index;
+                if ui
+                    .add_enabled(selected > 0, egui::Button::new("Move up"))
+                    .clicked()
+                {
+                    reorder = Some(SlotReorder::Swap(selected, selected - 1));
+                }
+                if ui
+                    .add_enabled(selected + 1 < slot_count, egui::Button::new("Move down"))
+                    .clicked()
+                {
+                    reorder = Some(SlotReorder::Swap(selected, selected + 1));
+                }
+            });
         });
 
         cols[1].vertical(
@@ -270,7 +3044,148 @@ fn enemies_contents(
                 }
             },
         );
-    })
+    });
+
+    // the selection follows the slot that was moved
+    match reorder {
+        Some(SlotReorder::Swap(slot, other)) => {
+            swap_slots(battle_structure, slot, other);
+            *enemy_selected_index = other;
+        }
+        Some(SlotReorder::Move(from, to)) => {
+            move_slot(battle_structure, from, to);
+            *enemy_selected_index = to;
+        }
+        None => {}
+    }
+}
+
+/// Enemy slot being dragged in the enemy list
+struct DraggedSlot(usize);
+
+enum SlotReorder {
+    Swap(usize, usize),
+    Move(usize, usize),
+}
+
+fn enemy_grid_contents(
+    ui: &mut egui::Ui,
+    battle_structure: &mut BattleStructure,
+    warnings: &[FormationWarning],
+    options: &mut BulkSetOptions,
+) {
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        egui::Grid::new("enemy_grid").striped(true).show(ui, |ui| {
+            ui.strong("Slot");
+            for field in EnemyField::ALL {
+                ui.strong(field.name());
+            }
+            ui.end_row();
+
+            for (i, enemy) in battle_structure.enemies.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(i.to_string());
+                    slot_warning_contents(ui, warnings, i);
+                });
+                for field in EnemyField::ALL {
+                    enemy_field_contents(ui, enemy, field, i);
+                }
+                ui.end_row();
+            }
+        });
+    });
+
+    ui.add_space(8.0);
+    ui.horizontal(|ui| {
+        ui.label("Set");
+        egui::ComboBox::from_id_source("bulk_set_field")
+            .selected_text(options.field.name())
+            .show_ui(ui, |ui| {
+                for field in EnemyField::ALL {
+                    ui.selectable_value(&mut options.field, field, field.name());
+                }
+            });
+        let range = options.field.range();
+        options.value = options.value.clamp(*range.start(), *range.end());
+        ui.label("to");
+        match options.field {
+            EnemyField::Id => {
+                egui::ComboBox::from_id_source("bulk_set_enemy")
+                    .selected_text(*ENEMY_NAMES.get(options.value as usize).unwrap_or(&""))
+                    .show_ui(ui, |ui| {
+                        (0..ENEMY_NAMES.len()).for_each(|i| {
+                            ui.selectable_value(&mut options.value, i as i32, ENEMY_NAMES[i]);
+                        });
+                    });
+            }
+            field if field.is_bool() => {
+                let mut value = options.value > 0;
+                ui.checkbox(&mut value, "");
+                options.value = value as i32;
+            }
+            _ => {
+                ui.add(egui::DragValue::new(&mut options.value).range(range));
+            }
+        }
+        ui.label("on");
+        egui::ComboBox::from_id_source("bulk_set_selection")
+            .selected_text(options.selection.name())
+            .show_ui(ui, |ui| {
+                for selection in [SlotSelection::All, SlotSelection::Enabled] {
+                    ui.selectable_value(&mut options.selection, selection, selection.name());
+                }
+            });
+        if ui.button("Apply").clicked() {
+            if let Err(err) =
+                bulk_set(battle_structure, options.field, options.value, options.selection)
+            {
+                execute(async move {
+                    error_dialog(&err.to_string()).await;
+                });
+            }
+        }
+    });
+}
+
+fn enemy_field_contents(ui: &mut egui::Ui, enemy: &mut Enemy, field: EnemyField, slot: usize) {
+    let mut value = field.value(enemy);
+    match field {
+        EnemyField::Id => {
+            egui::ComboBox::from_id_source(("enemy_grid_id", slot))
+                .width(140.0)
+                .selected_text(
+                    *ENEMY_NAMES
+                        .get(enemy.id as usize)
+                        .unwrap_or(&"Invalid enemy id!"),
+                )
+                .show_ui(ui, |ui| {
+                    (0..ENEMY_NAMES.len()).for_each(|i| {
+                        ui.selectable_value(&mut value, i as i32, ENEMY_NAMES[i]);
+                    });
+                });
+        }
+        field if field.is_bool() => {
+            let mut checked = value > 0;
+            ui.checkbox(&mut checked, "");
+            value = checked as i32;
+        }
+        EnemyField::Unknown1
+        | EnemyField::Unknown2
+        | EnemyField::Unknown3
+        | EnemyField::Unknown4 => {
+            ui.add(
+                egui::DragValue::new(&mut value)
+                    .range(field.range())
+                    .hexadecimal(1, false, true),
+            );
+        }
+        _ => {
+            ui.add(egui::DragValue::new(&mut value).range(field.range()));
+        }
+    }
+    if value != field.value(enemy) {
+        let _ = field.set(enemy, value);
+    }
 }
 
 fn enemy_contents(ui: &mut egui::Ui, enemy: &mut Enemy) {
@@ -328,6 +3243,27 @@ fn frame() -> egui::Frame {
         .outer_margin(4.0)
 }
 
+/// Prints the message about the command line, or shows it in a dialog on Windows where the
+/// GUI has no console to print to
+fn command_line_message(message: &str, is_error: bool) {
+    if cfg!(target_os = "windows") {
+        let level = match is_error {
+            true => rfd::MessageLevel::Error,
+            false => rfd::MessageLevel::Info,
+        };
+        rfd::MessageDialog::new()
+            .set_level(level)
+            .set_buttons(rfd::MessageButtons::Ok)
+            .set_title(APP_TITLE)
+            .set_description(message)
+            .show();
+    } else if is_error {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
 fn error_dialog(message: &str) -> impl Future<Output = rfd::MessageDialogResult> {
     AsyncMessageDialog::new()
         .set_level(rfd::MessageLevel::Error)
@@ -337,45 +3273,40 @@ fn error_dialog(message: &str) -> impl Future<Output = rfd::MessageDialogResult>
         .show()
 }
 
-fn execute<F: Future<Output = ()> + Send + 'static>(f: F) {
-    task::spawn(f);
+async fn confirm_discard_dialog() -> bool {
+    let result = AsyncMessageDialog::new()
+        .set_level(rfd::MessageLevel::Warning)
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .set_title("Unsaved changes")
+        .set_description("There are unsaved changes. Do you want to discard them?")
+        .show()
+        .await;
+    result == rfd::MessageDialogResult::Yes
 }
 
-fn read_battle_structures(bytes: &[u8]) -> anyhow::Result<Vec<BattleStructure>> {
-    if bytes.len() != size_of::<PackedBattleStructure>() * BATTLE_STRUCTURE_NUMBER {
-        return Err(anyhow::anyhow!("Incorrect bytes size"));
-    }
-
-    let mut battle_structure_list = Vec::with_capacity(BATTLE_STRUCTURE_NUMBER);
-    for i in 0..BATTLE_STRUCTURE_NUMBER {
-        let offset = i * size_of::<PackedBattleStructure>();
-        let packed_bs = PackedBattleStructure::try_from_bytes(
-            bytes
-                .get(offset..offset + size_of::<PackedBattleStructure>())
-                .ok_or(anyhow::anyhow!(
-                    "Could not retrieve data. File size not as expected!"
-                ))?,
-        )?;
-        battle_structure_list.push(packed_bs.into_battle_structure());
-    }
-    Ok(battle_structure_list)
+async fn confirm_overwrite_dialog(count: usize) -> bool {
+    let result = AsyncMessageDialog::new()
+        .set_level(rfd::MessageLevel::Warning)
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .set_title("Existing files")
+        .set_description(format!(
+            "{count} file(s) already exist in the folder. Do you want to replace them?"
+        ))
+        .show()
+        .await;
+    result == rfd::MessageDialogResult::Yes
 }
 
-fn write_packed_battle_structure(
-    battle_structure_list: &Vec<BattleStructure>,
-) -> anyhow::Result<Vec<u8>> {
-    let mut bytes: Vec<u8> =
-        Vec::with_capacity(BATTLE_STRUCTURE_NUMBER * size_of::<PackedBattleStructure>());
-
-    if battle_structure_list.len() != BATTLE_STRUCTURE_NUMBER {
-        return Err(anyhow::anyhow!(format!(
-            "Battle structure size is incorrect: {}",
-            battle_structure_list.len()
-        )));
-    }
+fn execute<F: Future<Output = ()> + Send + 'static>(f: F) {
+    task::spawn(f);
+}
 
-    for battle_structure in battle_structure_list {
-        bytes.extend_from_slice(battle_structure.as_packed_bytes()?.as_ref());
+/// Installations found under the home folder, only scanned on Linux
+fn find_installations() -> Vec<Installation> {
+    if !cfg!(target_os = "linux") {
+        return Vec::new();
     }
-    Ok(bytes)
+    std::env::var_os("HOME")
+        .map(|home| discover_installations(Path::new(&home)))
+        .unwrap_or_default()
 }